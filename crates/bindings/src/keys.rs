@@ -1,11 +1,7 @@
-use crate::CONTROLLER_KEY_MAP;
-
 use controller::ControllerButton;
 
 use io::Key;
-use std::sync::LazyLock;
-
-pub static JOYSTICK_KEYS: LazyLock<JoyStickKeys> = LazyLock::new(JoyStickKeys::default);
+use std::collections::HashMap;
 
 #[derive(Debug, Clone)]
 pub struct JoyStickKeys {
@@ -15,13 +11,13 @@ pub struct JoyStickKeys {
   starboard: Vec<Key>,
 }
 
-impl Default for JoyStickKeys {
-  fn default() -> Self {
+impl From<&HashMap<ControllerButton, Vec<Key>>> for JoyStickKeys {
+  fn from(map: &HashMap<ControllerButton, Vec<Key>>) -> Self {
     Self {
-      forward: CONTROLLER_KEY_MAP.get(&ControllerButton::Forward).cloned().unwrap_or_default(),
-      backward: CONTROLLER_KEY_MAP.get(&ControllerButton::Backward).cloned().unwrap_or_default(),
-      port: CONTROLLER_KEY_MAP.get(&ControllerButton::Port).cloned().unwrap_or_default(),
-      starboard: CONTROLLER_KEY_MAP.get(&ControllerButton::Starboard).cloned().unwrap_or_default(),
+      forward: map.get(&ControllerButton::Forward).cloned().unwrap_or_default(),
+      backward: map.get(&ControllerButton::Backward).cloned().unwrap_or_default(),
+      port: map.get(&ControllerButton::Port).cloned().unwrap_or_default(),
+      starboard: map.get(&ControllerButton::Starboard).cloned().unwrap_or_default(),
     }
   }
 }
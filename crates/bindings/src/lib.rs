@@ -2,46 +2,205 @@ mod keys;
 
 pub use keys::*;
 
-use controller::ControllerButton;
-use directories::BaseDirs;
-use io::{AlphabeticKey, ArrowKey, Key, ModifierKey, MouseKey, NumericKey, SystemKey};
+use controller::{ControllerButton, StickModifier};
+use io::{AlphabeticKey, ArrowKey, FunctionKey, Key, ModifierKey, MouseKey, NumericKey, SystemKey};
+use settings::{ConfigError, ConfigErrorReason, ConfigErrors, Handle, Schema, CONFIG_VERSION};
 
-use std::{collections::HashMap, sync::LazyLock};
+use std::{collections::HashMap, path::PathBuf, sync::LazyLock};
 
 use serde::{Deserialize, Serialize};
 
-#[rustfmt::skip]
-pub static CONTROLLER_KEY_MAP: LazyLock<HashMap<ControllerButton, Vec<Key>>> = LazyLock::new(|| {
-  let path = BaseDirs::new().unwrap().config_dir().join("joymouse/bindings.toml");
+pub type KeyMapHandle = Handle<KeyMap>;
+
+pub fn bindings_path() -> Option<PathBuf> {
+  settings::config_dir().map(|dir| dir.join("bindings.toml"))
+}
+
+pub fn load() -> Result<Bindings, ConfigErrors> {
+  let path = bindings_path().ok_or_else(|| {
+    ConfigError::new("bindings.toml", None, ConfigErrorReason::Io("no config directory".into()))
+  })?;
+  let origin = path.display().to_string();
 
   if !path.exists() {
+    let io =
+      |e: std::io::Error| ConfigError::new(&origin, None, ConfigErrorReason::Io(e.to_string()));
     let bindings = Bindings::default();
     let toml = toml::to_string(&bindings).expect("failed to serialize default bindings");
-    std::fs::create_dir_all(path.parent().unwrap()).expect("failed to create config directory");
-    std::fs::write(&path, toml).expect("failed to write default bindings file");
-    return bindings.0;
+    let toml = format!("version = {CONFIG_VERSION}\n\n{toml}");
+    if let Some(parent) = path.parent() {
+      std::fs::create_dir_all(parent).map_err(io)?;
+    }
+    std::fs::write(&path, toml).map_err(io)?;
+    return Ok(bindings);
   }
 
-  match std::fs::read_to_string(&path).ok().and_then(|s| toml::from_str::<Bindings>(&s).ok()) {
-    Some(bindings) => bindings.0,
-    None => {
-      eprintln!("Failed to load bindings from {:?}, falling back to defaults", path);
-      Bindings::default().0
+  let mut table = settings::read_table(&path)?;
+  settings::report_upgrade(&origin, settings::upgrade(&origin, &mut table, Schema::Bindings)?);
+  table.remove("version");
+
+  Bindings::parse(&origin, &table)
+}
+
+/// The active bindings, swapped in by whoever loads the configuration files.
+pub static BINDINGS: LazyLock<KeyMapHandle> = LazyLock::new(KeyMapHandle::default);
+
+/// Lookup tables derived from [`Bindings`].
+#[derive(Debug, Clone)]
+pub struct KeyMap {
+  controller_key_map: HashMap<ControllerButton, Vec<Key>>,
+  keyboard_button_map: HashMap<Key, ControllerButton>,
+  keyboard_modifier_map: HashMap<Key, StickModifier>,
+  joystick_keys: JoyStickKeys,
+}
+
+impl Default for KeyMap {
+  fn default() -> Self {
+    Self::from(Bindings::default())
+  }
+}
+
+impl From<Bindings> for KeyMap {
+  fn from(bindings: Bindings) -> Self {
+    let keyboard_button_map = bindings
+      .0
+      .iter()
+      .flat_map(|(button, keys)| keys.iter().map(move |key| (*key, *button)))
+      .collect();
+    let keyboard_modifier_map = bindings
+      .1
+      .iter()
+      .flat_map(|(modifier, keys)| keys.iter().map(move |key| (*key, *modifier)))
+      .collect();
+    let joystick_keys = JoyStickKeys::from(&bindings.0);
+    Self {
+      controller_key_map: bindings.0,
+      keyboard_button_map,
+      keyboard_modifier_map,
+      joystick_keys,
+    }
+  }
+}
+
+impl KeyMap {
+  pub fn keys(&self, button: &ControllerButton) -> &[Key] {
+    self.controller_key_map.get(button).map(Vec::as_slice).unwrap_or_default()
+  }
+
+  pub fn button(&self, key: &Key) -> Option<ControllerButton> {
+    self.keyboard_button_map.get(key).copied()
+  }
+
+  pub fn modifier(&self, key: &Key) -> Option<StickModifier> {
+    self.keyboard_modifier_map.get(key).copied()
+  }
+
+  pub fn joystick_keys(&self) -> &JoyStickKeys {
+    &self.joystick_keys
+  }
+}
+
+/// The keys bound to each controller button, and to each left stick modifier.
+#[derive(Debug, Clone)]
+pub struct Bindings(pub HashMap<ControllerButton, Vec<Key>>, pub HashMap<StickModifier, Vec<Key>>);
+
+/// Something that keys can be bound to in `bindings.toml`.
+enum Action {
+  Button(ControllerButton),
+  Modifier(StickModifier),
+}
+
+impl std::str::FromStr for Action {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    s.parse()
+      .map(Action::Button)
+      .or_else(|_| s.parse().map(Action::Modifier))
+      .map_err(|_| format!("Invalid action: {s}"))
+  }
+}
+
+impl Bindings {
+  /// Parses a table of `action = ["key", ...]` entries, reporting every invalid entry.
+  pub fn parse(origin: &str, table: &toml::Table) -> Result<Self, ConfigErrors> {
+    let mut errors = ConfigErrors::default();
+    let mut buttons = HashMap::new();
+    let mut modifiers = HashMap::new();
+
+    for (name, value) in table {
+      let error = |reason| ConfigError::new(origin, Some(name), reason);
+
+      let action = name.parse::<Action>().ok();
+      if action.is_none() {
+        errors.push(error(ConfigErrorReason::UnknownButton));
+      }
+
+      let Some(entries) = value.as_array() else {
+        errors.push(error(ConfigErrorReason::InvalidType(format!(
+          "expected an array of key names, found {value}"
+        ))));
+        continue;
+      };
+
+      let mut keys = Vec::with_capacity(entries.len());
+
+      for entry in entries {
+        match entry.as_str() {
+          Some(key) => match Key::try_from(key) {
+            Ok(key) => keys.push(key),
+            Err(_) => errors.push(error(ConfigErrorReason::UnknownKey(key.to_owned()))),
+          },
+          None => errors.push(error(ConfigErrorReason::InvalidType(format!(
+            "expected a key name, found {entry}"
+          )))),
+        }
+      }
+
+      match action {
+        Some(Action::Button(button)) => {
+          buttons.insert(button, keys);
+        }
+        Some(Action::Modifier(modifier)) => {
+          modifiers.insert(modifier, keys);
+        }
+        None => {}
+      }
+    }
+
+    // A key can only press a button or modify the left stick, not both.
+    for name in table.keys() {
+      let Ok(Action::Modifier(modifier)) = name.parse::<Action>() else {
+        continue;
+      };
+      for key in modifiers.get(&modifier).into_iter().flatten() {
+        if let Some(button) =
+          buttons.iter().find_map(|(button, keys)| keys.contains(key).then_some(button))
+        {
+          errors.push(ConfigError::new(
+            origin,
+            Some(name),
+            ConfigErrorReason::Invalid(format!("'{key}' is also bound to `{button}`")),
+          ));
+        }
+      }
     }
+
+    errors.into_result(Self(buttons, modifiers))
   }
-});
 
-#[rustfmt::skip]
-pub static KEYBOARD_BUTTON_MAP: LazyLock<HashMap<Key, ControllerButton>> = LazyLock::new(|| {
-  CONTROLLER_KEY_MAP
-    .iter()
-    .flat_map(|(button, keys)| keys.iter().map(move |key| (*key, *button)))
-    .collect()
-});
+  /// Replaces the keys of every button and modifier that is bound in `other`, and unbinds the
+  /// keys that `other` binds from every other button and modifier.
+  pub fn merge(&mut self, other: Bindings) {
+    let rebound: Vec<Key> = other.0.values().chain(other.1.values()).flatten().copied().collect();
+    for keys in self.0.values_mut().chain(self.1.values_mut()) {
+      keys.retain(|key| !rebound.contains(key));
+    }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(rename_all = "snake_case")]
-pub struct Bindings(#[serde(with = "bindings_map_format")] pub HashMap<ControllerButton, Vec<Key>>);
+    self.0.extend(other.0);
+    self.1.extend(other.1);
+  }
+}
 
 impl Default for Bindings {
   fn default() -> Self {
@@ -91,23 +250,21 @@ impl Default for Bindings {
       assert!(map.contains_key(button), "Missing mapping for ControllerButton::{:?}", button);
     }
 
-    Self(map)
+    let mut modifiers = HashMap::new();
+
+    modifiers.insert(StickModifier::Walk, vec![Modifier(ModifierKey::LeftShift)]);
+    modifiers.insert(StickModifier::Magnitude(1), vec![Function(FunctionKey::F1)]);
+    modifiers.insert(StickModifier::Magnitude(2), vec![Function(FunctionKey::F2)]);
+    modifiers.insert(StickModifier::Magnitude(3), vec![Function(FunctionKey::F3)]);
+
+    Self(map, modifiers)
   }
 }
 
-mod bindings_map_format {
-  use controller::ControllerButton;
-  use io::Key;
-  use serde::de::Error;
-  use serde::{Deserialize, Deserializer, Serializer};
-  use std::collections::HashMap;
-
-  pub fn serialize<S>(
-    map: &HashMap<ControllerButton, Vec<Key>>,
-    serializer: S,
-  ) -> Result<S::Ok, S::Error>
+impl Serialize for Bindings {
+  fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
   where
-    S: Serializer,
+    S: serde::Serializer,
   {
     use controller::ControllerButton::*;
     use serde::ser::SerializeMap;
@@ -117,37 +274,129 @@ mod bindings_map_format {
       L2, R2, L3, R3, Start, Select,
     ];
 
-    let mut ser = serializer.serialize_map(Some(map.len()))?;
+    let mut modifiers: Vec<_> = self.1.iter().collect();
+    modifiers.sort_by_key(|(modifier, _)| match modifier {
+      StickModifier::Walk => 0,
+      StickModifier::Magnitude(level) => *level,
+    });
+
+    let key_strs = |keys: &[Key]| keys.iter().map(|k| k.to_string()).collect::<Vec<String>>();
+    let mut ser = serializer.serialize_map(Some(self.0.len() + self.1.len()))?;
 
     for button in ordered_keys {
-      if let Some(keys) = map.get(&button) {
-        let key_strs: Vec<String> = keys.iter().map(|k| k.to_string()).collect();
-        ser.serialize_entry(&button.to_string(), &key_strs)?;
+      if let Some(keys) = self.0.get(&button) {
+        ser.serialize_entry(&button.to_string(), &key_strs(keys))?;
       }
     }
 
+    for (modifier, keys) in modifiers {
+      ser.serialize_entry(&modifier.to_string(), &key_strs(keys))?;
+    }
+
     ser.end()
   }
+}
 
-  pub fn deserialize<'de, D>(
-    deserializer: D,
-  ) -> Result<HashMap<ControllerButton, Vec<Key>>, D::Error>
+/// Deserializes through [`Bindings::parse`], so that serde callers get the same checks.
+impl<'de> Deserialize<'de> for Bindings {
+  fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
   where
-    D: Deserializer<'de>,
+    D: serde::Deserializer<'de>,
   {
-    let raw: HashMap<String, Vec<String>> = HashMap::deserialize(deserializer)?;
-    let mut result = HashMap::new();
+    let table = toml::Table::deserialize(deserializer)?;
+    Bindings::parse("bindings", &table).map_err(serde::de::Error::custom)
+  }
+}
 
-    for (button_str, key_strs) in raw {
-      let button = button_str.parse::<ControllerButton>().map_err(D::Error::custom)?;
-      let keys = key_strs
-        .into_iter()
-        .map(|s| Key::try_from(s.as_str()).map_err(D::Error::custom))
-        .collect::<Result<Vec<_>, _>>()?;
+#[cfg(test)]
+mod tests {
+  use super::*;
 
-      result.insert(button, keys);
-    }
+  #[test]
+  fn test_parse_valid_bindings() {
+    let table: toml::Table = toml::from_str(r#"south = ["space", "mouse_side"]"#).unwrap();
+    let bindings = Bindings::parse("bindings.toml", &table).unwrap();
+
+    assert_eq!(
+      bindings.0.get(&ControllerButton::South).unwrap(),
+      &vec![Key::System(SystemKey::Space), Key::Mouse(MouseKey::Side)]
+    );
+  }
 
-    Ok(result)
+  #[test]
+  fn test_parse_modifiers() {
+    let table: toml::Table =
+      toml::from_str("walk = [\"left_shift\"]\nmagnitude_2 = [\"f2\"]").unwrap();
+    let keymap = KeyMap::from(Bindings::parse("bindings.toml", &table).unwrap());
+
+    assert_eq!(keymap.modifier(&Key::Modifier(ModifierKey::LeftShift)), Some(StickModifier::Walk));
+    assert_eq!(keymap.modifier(&Key::Function(FunctionKey::F2)), Some(StickModifier::Magnitude(2)));
+    assert_eq!(keymap.button(&Key::Function(FunctionKey::F2)), None);
+  }
+
+  #[test]
+  fn test_parse_rejects_keys_bound_to_a_button_and_a_modifier() {
+    let table: toml::Table =
+      toml::from_str("l3 = [\"left_shift\"]\nwalk = [\"left_shift\"]").unwrap();
+
+    let errors = Bindings::parse("bindings.toml", &table).unwrap_err();
+
+    assert_eq!(errors.errors().len(), 1);
+    assert_eq!(errors.errors()[0].key(), Some("walk"));
+  }
+
+  #[test]
+  fn test_deserialize_rejects_keys_bound_to_a_button_and_a_modifier() {
+    let result = toml::from_str::<Bindings>("l3 = [\"left_shift\"]\nwalk = [\"left_shift\"]");
+
+    assert!(result.is_err());
+    assert!(toml::from_str::<Bindings>("l3 = [\"left_shift\"]").is_ok());
+  }
+
+  #[test]
+  fn test_merge_unbinds_keys_that_are_bound_again() {
+    let table: toml::Table = toml::from_str("l3 = [\"left_shift\"]").unwrap();
+    let mut bindings = Bindings::default();
+
+    bindings.merge(Bindings::parse("profile.toml", &table).unwrap());
+    let keymap = KeyMap::from(bindings);
+
+    let shift = Key::Modifier(ModifierKey::LeftShift);
+    assert_eq!(keymap.button(&shift), Some(ControllerButton::L3));
+    assert_eq!(keymap.modifier(&shift), None);
+    assert_eq!(keymap.keys(&ControllerButton::L3), [shift]);
+  }
+
+  #[test]
+  fn test_default_bindings_round_trip() {
+    let toml = toml::to_string(&Bindings::default()).unwrap();
+    let table: toml::Table = toml::from_str(&toml).unwrap();
+    let bindings = Bindings::parse("bindings.toml", &table).unwrap();
+
+    assert_eq!(bindings.0, Bindings::default().0);
+    assert_eq!(bindings.1, Bindings::default().1);
+  }
+
+  #[test]
+  fn test_parse_reports_every_invalid_entry() {
+    let table: toml::Table = toml::from_str(
+      r#"
+      south = ["space", "foobar"]
+      sout = ["e"]
+      north = "f"
+      "#,
+    )
+    .unwrap();
+
+    let errors = Bindings::parse("bindings.toml", &table).unwrap_err();
+    let errors: Vec<(Option<&str>, &ConfigErrorReason)> =
+      errors.errors().iter().map(|e| (e.key(), e.reason())).collect();
+
+    assert_eq!(errors.len(), 3);
+    assert!(matches!(errors[0], (Some("north"), ConfigErrorReason::InvalidType(_))));
+    assert!(matches!(errors[1], (Some("sout"), ConfigErrorReason::UnknownButton)));
+    assert!(
+      matches!(errors[2], (Some("south"), ConfigErrorReason::UnknownKey(key)) if key == "foobar")
+    );
   }
 }
@@ -46,6 +46,11 @@ impl ControllerButton {
       Start, Select,
     ]
   }
+
+  pub fn is_joystick(&self) -> bool {
+    use ControllerButton::*;
+    matches!(self, Forward | Backward | Starboard | Port)
+  }
 }
 
 impl std::fmt::Display for ControllerButton {
@@ -0,0 +1,70 @@
+use std::{
+  sync::Mutex,
+  time::{Duration, Instant},
+};
+
+/// The source of the current time for everything that depends on timing.
+pub trait Clock: std::fmt::Debug + Send + Sync {
+  fn now(&self) -> Instant;
+}
+
+/// The wall clock, used outside of tests.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+  fn now(&self) -> Instant {
+    Instant::now()
+  }
+}
+
+/// A clock that only moves when it is told to, for reproducible timing in tests and simulations.
+#[derive(Debug)]
+pub struct ManualClock {
+  now: Mutex<Instant>,
+}
+
+impl Default for ManualClock {
+  fn default() -> Self {
+    Self::new(Instant::now())
+  }
+}
+
+impl ManualClock {
+  pub fn new(start: Instant) -> Self {
+    Self {
+      now: Mutex::new(start),
+    }
+  }
+
+  pub fn advance(&self, duration: Duration) {
+    *self.now.lock().unwrap() += duration;
+  }
+
+  pub fn set(&self, now: Instant) {
+    *self.now.lock().unwrap() = now;
+  }
+}
+
+impl Clock for ManualClock {
+  fn now(&self) -> Instant {
+    *self.now.lock().unwrap()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_manual_clock_only_moves_when_advanced() {
+    let clock = ManualClock::default();
+    let start = clock.now();
+
+    assert_eq!(clock.now(), start);
+
+    clock.advance(Duration::from_millis(16));
+
+    assert_eq!(clock.now() - start, Duration::from_millis(16));
+  }
+}
@@ -1,9 +1,10 @@
-use crate::{ButtonEvent, JoyStickEvent};
+use crate::{ButtonEvent, JoyStickEvent, TriggerEvent};
 
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub enum ControllerEvent {
   Button(ButtonEvent),
   JoyStick(JoyStickEvent),
+  Trigger(TriggerEvent),
 }
 
 impl From<ButtonEvent> for ControllerEvent {
@@ -17,3 +18,9 @@ impl From<JoyStickEvent> for ControllerEvent {
     Self::JoyStick(v)
   }
 }
+
+impl From<TriggerEvent> for ControllerEvent {
+  fn from(v: TriggerEvent) -> Self {
+    Self::Trigger(v)
+  }
+}
@@ -0,0 +1,51 @@
+use crate::joystick::vector::Vector;
+
+use settings::Edge;
+
+use std::{collections::VecDeque, time::Instant};
+
+/// Latches the stick at its tilt once the mouse travelled far enough in one direction, and holds
+/// it there until the mouse moves back the other way.
+#[derive(Debug, Clone, Default)]
+pub struct EdgeHold {
+  travel: VecDeque<(Instant, Vector)>,
+  latched: Option<Vector>,
+}
+
+impl EdgeHold {
+  /// Feeds the mouse movement `delta` together with the stick position the mapper made of it, and
+  /// returns the position to emit.
+  pub fn hold(&mut self, delta: Vector, position: Vector, now: Instant, edge: &Edge) -> Vector {
+    if !edge.is_enabled() {
+      *self = Self::default();
+      return position;
+    }
+
+    if let Some(latched) = self.latched {
+      if delta.dx() * latched.dx() + delta.dy() * latched.dy() >= 0.0 {
+        return latched;
+      }
+      *self = Self::default();
+      return position;
+    }
+
+    self.travel.push_back((now, delta));
+    while self.travel.front().is_some_and(|(time, _)| now.duration_since(*time) > edge.window()) {
+      self.travel.pop_front();
+    }
+
+    let travel: Vec<Vector> = self.travel.iter().map(|(_, delta)| *delta).collect();
+    let travel = Vector::sum(&travel);
+    let is_centered = position.dx() == 0.0 && position.dy() == 0.0;
+    if travel.dx().hypot(travel.dy()) >= edge.distance() && !is_centered {
+      self.travel.clear();
+      self.latched = Some(position);
+    }
+
+    position
+  }
+
+  pub fn is_latched(&self) -> bool {
+    self.latched.is_some()
+  }
+}
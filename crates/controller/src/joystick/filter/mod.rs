@@ -0,0 +1,135 @@
+mod smoothing;
+mod stabilizer;
+
+pub use smoothing::{Ema, Median, OneEuro};
+pub use stabilizer::{AngleLock, MagnitudeLock};
+
+use crate::joystick::vector::Vector;
+
+use settings::{Filter, FilterKind};
+
+use std::time::Instant;
+
+/// A stage of a filter chain that smooths or stabilizes a stream of vectors.
+pub trait VectorFilter: std::fmt::Debug + Send {
+  /// Feeds the `vector` that arrived at `timestamp` and returns the filtered one.
+  fn apply(&mut self, vector: Vector, timestamp: Instant) -> Vector;
+}
+
+/// Creates the filter configured in `filter`.
+pub fn filter(filter: &Filter) -> Box<dyn VectorFilter> {
+  match filter.kind() {
+    FilterKind::Ema => Box::new(Ema::new(filter.alpha())),
+    FilterKind::OneEuro => {
+      Box::new(OneEuro::new(filter.min_cutoff(), filter.beta(), filter.derivative_cutoff()))
+    }
+    FilterKind::Median => Box::new(Median::new(filter.window())),
+    FilterKind::AngleLock => Box::new(AngleLock::new(filter.limit())),
+    FilterKind::MagnitudeLock => Box::new(MagnitudeLock::new(filter.threshold())),
+  }
+}
+
+/// Runs vectors through a configured list of filters in order.
+///
+/// The filters are built again whenever the configuration changes, which forgets their state.
+#[derive(Debug)]
+pub struct FilterChain {
+  config: Vec<Filter>,
+  filters: Vec<Box<dyn VectorFilter>>,
+  output: Vector,
+  settle: f64,
+}
+
+impl Default for FilterChain {
+  fn default() -> Self {
+    Self {
+      config: Default::default(),
+      filters: Default::default(),
+      output: Default::default(),
+      settle: 0.5,
+    }
+  }
+}
+
+impl FilterChain {
+  /// Snaps the result to the input once it is within `settle` of it on both axes, half a unit of
+  /// stick tilt by default. A `settle` of `0.0` never snaps.
+  pub fn with_settle(mut self, settle: f64) -> Self {
+    self.settle = settle;
+    self
+  }
+
+  /// Runs `vector` through the filters configured in `config`.
+  ///
+  /// Once the result is close enough to `vector` it snaps to it, so that filters which only
+  /// approach their input still settle.
+  pub fn apply(&mut self, vector: Vector, timestamp: Instant, config: &[Filter]) -> Vector {
+    if self.config != config {
+      self.config = config.to_vec();
+      self.filters = config.iter().map(filter).collect();
+    }
+
+    let output =
+      self.filters.iter_mut().fold(vector, |vector, filter| filter.apply(vector, timestamp));
+    let settled = (output.dx() - vector.dx()).abs() < self.settle
+      && (output.dy() - vector.dy()).abs() < self.settle;
+
+    self.output = if settled {
+      vector
+    } else {
+      output
+    };
+    self.output
+  }
+
+  /// The result of the last call to [`FilterChain::apply`].
+  pub fn output(&self) -> Vector {
+    self.output
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_filters_are_applied_in_order() {
+    let config = [
+      Filter::new(FilterKind::Median).with_window(3),
+      Filter::new(FilterKind::Ema).with_alpha(0.5),
+    ];
+    let mut chain = FilterChain::default();
+    let now = Instant::now();
+
+    chain.apply(Vector::new(100.0, 0.0), now, &config);
+    chain.apply(Vector::new(100.0, 0.0), now, &config);
+    let vector = chain.apply(Vector::new(10000.0, 0.0), now, &config);
+
+    // The median drops the outlier before the moving average ever sees it.
+    assert_eq!(vector.dx(), 100.0);
+  }
+
+  #[test]
+  fn test_output_settles_on_the_input() {
+    let config = [Filter::new(FilterKind::Ema).with_alpha(0.5)];
+    let mut chain = FilterChain::default();
+    let now = Instant::now();
+
+    chain.apply(Vector::new(100.0, 0.0), now, &config);
+    let vector =
+      (0..10).fold(Vector::default(), |_, _| chain.apply(Vector::default(), now, &config));
+
+    assert_eq!((vector.dx(), vector.dy()), (0.0, 0.0));
+  }
+
+  #[test]
+  fn test_output_without_settling_keeps_smoothing_small_values() {
+    let config = [Filter::new(FilterKind::Ema).with_alpha(0.5)];
+    let mut chain = FilterChain::default().with_settle(0.0);
+    let now = Instant::now();
+
+    chain.apply(Vector::new(2.0, 0.0), now, &config);
+
+    assert_eq!(chain.apply(Vector::new(2.5, 0.0), now, &config).dx(), 2.25);
+  }
+}
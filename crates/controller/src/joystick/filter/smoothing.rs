@@ -0,0 +1,173 @@
+use crate::joystick::{filter::VectorFilter, vector::Vector};
+
+use std::{collections::VecDeque, f64::consts::PI, time::Instant};
+
+/// Exponential moving average, which moves `alpha` of the way towards each new vector.
+#[derive(Debug, Clone)]
+pub struct Ema {
+  alpha: f64,
+  state: Option<Vector>,
+}
+
+impl Ema {
+  pub fn new(alpha: f64) -> Self {
+    Self {
+      alpha,
+      state: None,
+    }
+  }
+}
+
+impl VectorFilter for Ema {
+  fn apply(&mut self, vector: Vector, _: Instant) -> Vector {
+    let state = match self.state {
+      Some(previous) => Vector::new(
+        previous.dx() + self.alpha * (vector.dx() - previous.dx()),
+        previous.dy() + self.alpha * (vector.dy() - previous.dy()),
+      ),
+      None => vector,
+    };
+    self.state = Some(state);
+    state
+  }
+}
+
+/// The 1€ filter by Casiez et al., a low-pass filter whose cutoff frequency rises with the speed
+/// of the input, so that slow movement is smoothed while fast movement keeps little lag.
+#[derive(Debug, Clone)]
+pub struct OneEuro {
+  min_cutoff: f64,
+  beta: f64,
+  derivative_cutoff: f64,
+  state: Option<(Instant, Vector, Vector)>,
+}
+
+impl OneEuro {
+  pub fn new(min_cutoff: f64, beta: f64, derivative_cutoff: f64) -> Self {
+    Self {
+      min_cutoff,
+      beta,
+      derivative_cutoff,
+      state: None,
+    }
+  }
+
+  /// The smoothing factor of a low-pass filter with the cutoff frequency `cutoff` for samples
+  /// that are `elapsed` seconds apart.
+  fn alpha(cutoff: f64, elapsed: f64) -> f64 {
+    let tau = (2.0 * PI * cutoff).recip();
+    (1.0 + tau / elapsed).recip()
+  }
+
+  fn lerp(from: Vector, to: Vector, alpha: f64) -> Vector {
+    Vector::new(
+      from.dx() + alpha * (to.dx() - from.dx()),
+      from.dy() + alpha * (to.dy() - from.dy()),
+    )
+  }
+}
+
+impl VectorFilter for OneEuro {
+  fn apply(&mut self, vector: Vector, timestamp: Instant) -> Vector {
+    let Some((last, previous, derivative)) = self.state else {
+      self.state = Some((timestamp, vector, Vector::default()));
+      return vector;
+    };
+
+    // Samples that arrive at the same time are treated as a millisecond apart.
+    let elapsed = timestamp.saturating_duration_since(last).as_secs_f64().max(0.001);
+    let speed =
+      Vector::new((vector.dx() - previous.dx()) / elapsed, (vector.dy() - previous.dy()) / elapsed);
+    let derivative = Self::lerp(derivative, speed, Self::alpha(self.derivative_cutoff, elapsed));
+    let cutoff = self.min_cutoff + self.beta * derivative.dx().hypot(derivative.dy());
+    let filtered = Self::lerp(previous, vector, Self::alpha(cutoff, elapsed));
+
+    self.state = Some((timestamp, filtered, derivative));
+    filtered
+  }
+}
+
+/// The median of each axis over the last `window` vectors.
+#[derive(Debug, Clone)]
+pub struct Median {
+  window: usize,
+  history: VecDeque<Vector>,
+}
+
+impl Median {
+  pub fn new(window: usize) -> Self {
+    Self {
+      window: window.max(1),
+      history: VecDeque::new(),
+    }
+  }
+
+  fn median(mut values: Vec<f64>) -> f64 {
+    values.sort_by(f64::total_cmp);
+    let middle = values.len() / 2;
+    if values.len() % 2 == 0 {
+      (values[middle - 1] + values[middle]) / 2.0
+    } else {
+      values[middle]
+    }
+  }
+}
+
+impl VectorFilter for Median {
+  fn apply(&mut self, vector: Vector, _: Instant) -> Vector {
+    self.history.push_back(vector);
+    if self.history.len() > self.window {
+      self.history.pop_front();
+    }
+    Vector::new(
+      Self::median(self.history.iter().map(Vector::dx).collect()),
+      Self::median(self.history.iter().map(Vector::dy).collect()),
+    )
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use std::time::Duration;
+
+  #[test]
+  fn test_ema_moves_part_of_the_way() {
+    let mut ema = Ema::new(0.25);
+    let now = Instant::now();
+
+    ema.apply(Vector::new(0.0, 100.0), now);
+
+    assert_eq!(ema.apply(Vector::new(100.0, 100.0), now).dx(), 25.0);
+  }
+
+  #[test]
+  fn test_one_euro_follows_fast_movement_more_closely() {
+    let start = Instant::now();
+    let mut slow = OneEuro::new(1.0, 0.0, 1.0);
+    let mut fast = OneEuro::new(1.0, 0.01, 1.0);
+
+    let step = |filter: &mut OneEuro| {
+      (1..=10).fold(Vector::default(), |_, i| {
+        filter.apply(Vector::new(1000.0 * i as f64, 0.0), start + Duration::from_millis(i))
+      })
+    };
+    let (slow, fast) = (step(&mut slow), step(&mut fast));
+
+    assert!(slow.dx() < fast.dx());
+    assert!(fast.dx() <= 10000.0);
+  }
+
+  #[test]
+  fn test_median_drops_outliers() {
+    let mut median = Median::new(3);
+    let now = Instant::now();
+
+    median.apply(Vector::new(1.0, -1.0), now);
+    median.apply(Vector::new(2.0, -2.0), now);
+    let vector = median.apply(Vector::new(500.0, -3.0), now);
+
+    assert_eq!((vector.dx(), vector.dy()), (2.0, -2.0));
+  }
+}
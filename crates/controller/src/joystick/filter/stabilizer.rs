@@ -0,0 +1,109 @@
+use crate::joystick::{filter::VectorFilter, vector::Vector};
+
+use std::time::Instant;
+
+/// Keeps the previous direction while it changes by less than `limit` degrees.
+#[derive(Debug, Clone)]
+pub struct AngleLock {
+  limit: f64,
+  angle: Option<f64>,
+}
+
+impl AngleLock {
+  pub fn new(limit: f64) -> Self {
+    Self {
+      limit,
+      angle: None,
+    }
+  }
+}
+
+impl VectorFilter for AngleLock {
+  fn apply(&mut self, vector: Vector, _: Instant) -> Vector {
+    let length = vector.dx().hypot(vector.dy());
+    if length == 0.0 {
+      self.angle = None;
+      return vector;
+    }
+
+    let angle = vector.dy().atan2(vector.dx()).to_degrees();
+    let angle = match self.angle {
+      Some(previous)
+        if ((angle - previous + 180.0).rem_euclid(360.0) - 180.0).abs() < self.limit =>
+      {
+        previous
+      }
+      _ => angle,
+    };
+    self.angle = Some(angle);
+
+    let radians = angle.to_radians();
+    Vector::new(length * radians.cos(), length * radians.sin())
+  }
+}
+
+/// Keeps the previous length while it changes by less than `threshold`.
+#[derive(Debug, Clone)]
+pub struct MagnitudeLock {
+  threshold: f64,
+  length: Option<f64>,
+}
+
+impl MagnitudeLock {
+  pub fn new(threshold: f64) -> Self {
+    Self {
+      threshold,
+      length: None,
+    }
+  }
+}
+
+impl VectorFilter for MagnitudeLock {
+  fn apply(&mut self, vector: Vector, _: Instant) -> Vector {
+    let length = vector.dx().hypot(vector.dy());
+    if length == 0.0 {
+      self.length = None;
+      return vector;
+    }
+
+    let length = match self.length {
+      Some(previous) if (length - previous).abs() < self.threshold => previous,
+      _ => length,
+    };
+    self.length = Some(length);
+
+    vector * (length / vector.dx().hypot(vector.dy()))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_angle_lock_keeps_small_changes_of_direction() {
+    let mut lock = AngleLock::new(5.0);
+    let now = Instant::now();
+
+    lock.apply(Vector::new(100.0, 0.0), now);
+    let kept = lock.apply(Vector::new(100.0, 5.0), now);
+    let turned = lock.apply(Vector::new(0.0, 100.0), now);
+
+    assert_eq!(kept.dy(), 0.0);
+    assert!((kept.dx() - 100.0f64.hypot(5.0)).abs() < 1e-9);
+    assert!(turned.dx().abs() < 1e-9);
+  }
+
+  #[test]
+  fn test_magnitude_lock_keeps_small_changes_of_length() {
+    let mut lock = MagnitudeLock::new(50.0);
+    let now = Instant::now();
+
+    lock.apply(Vector::new(1000.0, 0.0), now);
+    let kept = lock.apply(Vector::new(0.0, -1040.0), now);
+
+    assert!(kept.dx().abs() < 1e-9);
+    assert!((kept.dy() + 1000.0).abs() < 1e-9);
+    assert_eq!(lock.apply(Vector::default(), now).dx(), 0.0);
+  }
+}
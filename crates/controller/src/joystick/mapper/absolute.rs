@@ -0,0 +1,140 @@
+use crate::joystick::{direction::Direction, mapper::StickMapper, vector::Vector};
+
+use settings::{ControllerSettings, MAX_STICK_TILT};
+
+use std::time::Instant;
+
+/// Moves a virtual stick by the mouse movement within a circle of `radius` mouse counts and
+/// tilts the stick as far as the virtual stick is away from the center.
+///
+/// The position is held until the mouse moves it back, unless `spring` pulls it back over time.
+#[derive(Debug, Clone)]
+pub struct AbsoluteMapper {
+  x: f64,
+  y: f64,
+  last_update: Instant,
+}
+
+impl AbsoluteMapper {
+  pub fn new(now: Instant) -> Self {
+    Self {
+      x: Default::default(),
+      y: Default::default(),
+      last_update: now,
+    }
+  }
+
+  fn vector(&self, settings: &ControllerSettings) -> Vector {
+    let scale = MAX_STICK_TILT / settings.absolute().radius();
+    Vector::new(self.x * scale, self.y * scale)
+  }
+
+  /// Pulls the virtual stick towards the center for the time since the last update.
+  fn spring(&mut self, now: Instant, settings: &ControllerSettings) {
+    let elapsed = now.saturating_duration_since(self.last_update).as_secs_f64();
+    self.last_update = now;
+
+    let length = self.x.hypot(self.y);
+    let pull = settings.absolute().spring() * elapsed;
+    if length == 0.0 || pull == 0.0 {
+      return;
+    }
+
+    let scale = (length - pull).max(0.0) / length;
+    self.x *= scale;
+    self.y *= scale;
+  }
+}
+
+impl StickMapper for AbsoluteMapper {
+  fn map(&mut self, delta: Vector, timestamp: Instant, settings: &ControllerSettings) -> Vector {
+    self.spring(timestamp, settings);
+
+    let radius = settings.absolute().radius();
+    let (x, y) = (self.x + delta.dx(), self.y + delta.dy());
+    let length = x.hypot(y);
+    let scale = if length > radius {
+      radius / length
+    } else {
+      1.0
+    };
+
+    self.x = x * scale;
+    self.y = y * scale;
+    self.vector(settings)
+  }
+
+  fn tick(
+    &mut self,
+    now: Instant,
+    _: Option<Direction>,
+    settings: &ControllerSettings,
+  ) -> Option<Vector> {
+    let previous = (self.x, self.y);
+    self.spring(now, settings);
+    (previous != (self.x, self.y)).then(|| self.vector(settings))
+  }
+
+  fn reset(&mut self, now: Instant) {
+    *self = Self::new(now);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use std::time::Duration;
+
+  fn ms(millis: u64) -> Duration {
+    Duration::from_millis(millis)
+  }
+
+  #[test]
+  fn test_position_is_held_until_moved_back() {
+    let settings = ControllerSettings::from_toml("test", "[absolute]\nradius = 100.0").unwrap();
+    let start = Instant::now();
+    let mut mapper = AbsoluteMapper::new(start);
+
+    let vector = mapper.map(Vector::new(50.0, -25.0), start, &settings);
+
+    assert_eq!((vector.dx(), vector.dy()), (MAX_STICK_TILT / 2.0, -MAX_STICK_TILT / 4.0));
+    assert!(mapper.tick(start + ms(1000), None, &settings).is_none());
+
+    let vector = mapper.map(Vector::new(-50.0, 25.0), start + ms(1000), &settings);
+
+    assert_eq!((vector.dx(), vector.dy()), (0.0, 0.0));
+  }
+
+  #[test]
+  fn test_position_stays_within_the_radius() {
+    let settings = ControllerSettings::from_toml("test", "[absolute]\nradius = 100.0").unwrap();
+    let start = Instant::now();
+    let mut mapper = AbsoluteMapper::new(start);
+
+    mapper.map(Vector::new(300.0, 400.0), start, &settings);
+    let vector = mapper.map(Vector::new(-60.0, 0.0), start, &settings);
+
+    // The movement back starts from the edge of the circle, not from where the mouse went.
+    assert!(vector.dx().abs() < 1e-6);
+    assert!((vector.dy() - 0.8 * MAX_STICK_TILT).abs() < 1e-6);
+  }
+
+  #[test]
+  fn test_spring_pulls_back_to_the_center() {
+    let settings =
+      ControllerSettings::from_toml("test", "[absolute]\nradius = 100.0\nspring = 100.0").unwrap();
+    let start = Instant::now();
+    let mut mapper = AbsoluteMapper::new(start);
+    mapper.map(Vector::new(0.0, 80.0), start, &settings);
+
+    let vector = mapper.tick(start + ms(500), None, &settings).unwrap();
+
+    assert!((vector.dy() - 0.3 * MAX_STICK_TILT).abs() < 1e-6);
+
+    let vector = mapper.tick(start + ms(1000), None, &settings).unwrap();
+
+    assert_eq!((vector.dx(), vector.dy()), (0.0, 0.0));
+    assert!(mapper.tick(start + ms(1500), None, &settings).is_none());
+  }
+}
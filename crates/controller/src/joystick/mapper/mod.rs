@@ -0,0 +1,56 @@
+mod absolute;
+mod motion;
+mod rate;
+
+pub use absolute::AbsoluteMapper;
+pub use motion::MotionMapper;
+pub use rate::RateMapper;
+
+use crate::joystick::{direction::Direction, vector::Vector};
+
+use settings::{ControllerSettings, MapperKind};
+
+use std::time::Instant;
+
+/// An algorithm that turns mouse movement into right stick positions.
+///
+/// Positions are given in stick units, i.e. within `MIN_STICK_TILT..=MAX_STICK_TILT`, before any
+/// deadzone compensation is applied.
+pub trait StickMapper: std::fmt::Debug + Send {
+  /// Feeds the mouse movement `delta` that arrived at `timestamp` and returns the stick position.
+  fn map(&mut self, delta: Vector, timestamp: Instant, settings: &ControllerSettings) -> Vector;
+
+  /// Called periodically while no mouse movement arrives.
+  ///
+  /// Returns the new stick position if it changed since the last call, e.g. because the mouse
+  /// stopped moving and the stick was recentered.
+  fn tick(
+    &mut self,
+    now: Instant,
+    left_stick: Option<Direction>,
+    settings: &ControllerSettings,
+  ) -> Option<Vector>;
+
+  /// Forgets every previous movement and returns to the center.
+  fn reset(&mut self, now: Instant);
+
+  /// The time at which the mapper wants to be ticked, if that is before the next regular tick.
+  fn deadline(&self) -> Option<Instant> {
+    None
+  }
+
+  /// Whether the mapper runs the stick filters itself, so that its positions are not filtered
+  /// again.
+  fn filters_positions(&self) -> bool {
+    false
+  }
+}
+
+/// Creates the mapper selected by `kind`, starting at `now`.
+pub fn mapper(kind: MapperKind, now: Instant) -> Box<dyn StickMapper> {
+  match kind {
+    MapperKind::Motion => Box::new(MotionMapper::new(now)),
+    MapperKind::Rate => Box::new(RateMapper::new(now)),
+    MapperKind::Absolute => Box::new(AbsoluteMapper::new(now)),
+  }
+}
@@ -0,0 +1,564 @@
+use crate::joystick::{
+  direction::Direction, filter::FilterChain, mapper::StickMapper, motion::Motion, vector::Vector,
+};
+
+use settings::{ControllerSettings, FlickMode, RecenterMode, MAX_STICK_TILT, MOTION_SMOOTHING};
+
+use std::time::{Duration, Instant};
+
+/// A full tilt towards `direction` that is held until `end`.
+#[derive(Debug, Clone, Copy)]
+struct Pulse {
+  direction: Vector,
+  end: Instant,
+}
+
+/// Classifies mouse movement as micro, macro or flick motion and moves the stick towards a tilt
+/// that depends on the speed, passing it through the stick filters once per tick.
+///
+/// With `preserve_distance`, the difference between the tilt a tick asked for and the position the
+/// stick filters settled on is kept as a residual. It is added to the next tick and
+/// paid out once the mouse stops, and the stick recenters as soon as a tick passes without
+/// anything left to emit.
+///
+/// With `flick = "pulse"`, a flick instead holds a full tilt for as long as the camera needs to
+/// turn by the angle the mouse moved during the flick, and then recenters.
+///
+/// Once the mouse is idle for the timeout of the current motion, the stick recenters as set in
+/// `[recenter]`, either at once or over the following ticks.
+#[derive(Debug)]
+pub struct MotionMapper {
+  x: f64,
+  y: f64,
+  motion: Motion,
+  motion_history: Vec<f64>,
+  smoothing: FilterChain,
+  last_event: Instant,
+  tick_start: Instant,
+  mouse_events: Vec<Vector>,
+  /// Kept as plain components, since a [`Vector`] is clamped to a full tilt.
+  residual: (f64, f64),
+  pulse: Option<Pulse>,
+  recentering: Option<Instant>,
+}
+
+impl MotionMapper {
+  pub fn new(now: Instant) -> Self {
+    Self {
+      x: Default::default(),
+      y: Default::default(),
+      motion: Default::default(),
+      motion_history: Default::default(),
+      smoothing: Default::default(),
+      last_event: now,
+      tick_start: now,
+      mouse_events: Default::default(),
+      residual: Default::default(),
+      pulse: Default::default(),
+      recentering: Default::default(),
+    }
+  }
+}
+
+impl StickMapper for MotionMapper {
+  fn map(&mut self, delta: Vector, timestamp: Instant, settings: &ControllerSettings) -> Vector {
+    if let Some(pulse) = &mut self.pulse {
+      if timestamp < pulse.end {
+        let counts = delta.dx() * pulse.direction.dx() + delta.dy() * pulse.direction.dy();
+        pulse.end += Self::turn_time(counts.max(0.0), settings);
+        return self.vector();
+      }
+      self.reset(timestamp);
+    }
+
+    self.mouse_events.push(delta);
+
+    if self.mouse_events.len() >= 2 {
+      let speed = self.compute_speed(settings);
+      self.motion = self.motion_from_speed(speed, settings);
+
+      match self.motion {
+        Motion::Flick if settings.flick() == FlickMode::Pulse => {
+          return self.start_pulse(timestamp, settings);
+        }
+        Motion::Flick => {
+          return self.commit(timestamp, settings);
+        }
+        Motion::Micro => {
+          self.last_event = timestamp;
+        }
+        _ => (),
+      }
+    }
+
+    if timestamp.duration_since(self.tick_start) >= settings.tickrate() {
+      return self.commit(timestamp, settings);
+    }
+
+    self.vector()
+  }
+
+  fn tick(
+    &mut self,
+    now: Instant,
+    left_stick: Option<Direction>,
+    settings: &ControllerSettings,
+  ) -> Option<Vector> {
+    if let Some(pulse) = self.pulse {
+      if now < pulse.end {
+        return None;
+      }
+      self.reset(now);
+      return Some(self.vector());
+    }
+
+    if settings.preserve_distance() {
+      if now.duration_since(self.tick_start) < settings.tickrate() {
+        return None;
+      }
+      let previous = self.vector();
+      let vector = self.commit(now, settings);
+      return (vector.dx() != previous.dx() || vector.dy() != previous.dy()).then_some(vector);
+    }
+
+    let timeout = self.motion.idle_timeout(settings, left_stick.is_some());
+    let is_centered = self.x == 0.0 && self.y == 0.0;
+    if is_centered {
+      return None;
+    }
+    if self.recentering.is_none() {
+      if now.duration_since(self.last_event) <= timeout {
+        return None;
+      }
+      self.recentering = Some(self.last_event + timeout);
+    }
+    Some(self.recenter(now, settings))
+  }
+
+  fn reset(&mut self, now: Instant) {
+    *self = Self::new(now);
+  }
+
+  fn deadline(&self) -> Option<Instant> {
+    self.pulse.map(|pulse| pulse.end)
+  }
+
+  fn filters_positions(&self) -> bool {
+    true
+  }
+}
+
+impl MotionMapper {
+  fn vector(&self) -> Vector {
+    Vector::new(self.x, self.y)
+  }
+
+  fn commit(&mut self, now: Instant, settings: &ControllerSettings) -> Vector {
+    self.tick_start = now;
+    self.last_event = now;
+    self.recentering = None;
+    let preserve_distance = settings.preserve_distance();
+    if preserve_distance && self.mouse_events.is_empty() {
+      return self.pay_out();
+    }
+    if self.mouse_events.len() < 2 && !preserve_distance {
+      return self.vector();
+    }
+    let vector = self.target(Vector::sum(&self.mouse_events), settings);
+    if preserve_distance {
+      let (x, y) = (vector.dx() + self.residual.0, vector.dy() + self.residual.1);
+      self.update_smoothed_position(Vector::new(x, y), now, settings);
+      self.residual = (x - self.x, y - self.y);
+    } else {
+      self.update_smoothed_position(vector, now, settings);
+    }
+    self.mouse_events.clear();
+    self.vector()
+  }
+
+  /// The tilt the mouse movement of a tick asks for, before any smoothing.
+  fn target(&self, movement: Vector, settings: &ControllerSettings) -> Vector {
+    let tilt = if self.motion == Motion::Flick {
+      settings.max_tilt_range()
+    } else {
+      let normalized_speed = Self::calculate_normalized_speed(&movement, settings);
+      let min = settings.min_tilt_range();
+      let max = settings.max_tilt_range();
+      min + (max - min) * settings.curve().apply(normalized_speed)
+    };
+    Self::compute_tilt_vector(movement, tilt, settings)
+  }
+
+  /// Moves the stick towards the center for the time since the last step, and forgets every
+  /// previous movement once it gets there.
+  fn recenter(&mut self, now: Instant, settings: &ControllerSettings) -> Vector {
+    let since = self.recentering.unwrap_or(now);
+    let elapsed = now.saturating_duration_since(since).as_secs_f64();
+    self.recentering = Some(now);
+
+    let recenter = settings.recenter();
+    let length = self.x.hypot(self.y);
+    let remaining = match recenter.mode() {
+      RecenterMode::Instant => 0.0,
+      RecenterMode::Decay => length * 0.5f64.powf(elapsed / recenter.half_life().as_secs_f64()),
+      RecenterMode::Inertia => length - recenter.deceleration() * MAX_STICK_TILT * elapsed,
+    };
+
+    if remaining < 1.0 {
+      self.reset(now);
+    } else {
+      let scale = remaining / length;
+      self.x *= scale;
+      self.y *= scale;
+    }
+    self.vector()
+  }
+
+  /// Tilts the stick fully towards the movement of the flick so far, for as long as the camera
+  /// needs to turn by it.
+  fn start_pulse(&mut self, now: Instant, settings: &ControllerSettings) -> Vector {
+    let vector = Vector::sum(&self.mouse_events);
+    let counts = vector.dx().hypot(vector.dy());
+    if counts == 0.0 {
+      return self.commit(now, settings);
+    }
+    let direction = vector * counts.recip();
+    self.pulse = Some(Pulse {
+      direction,
+      end: now + Self::turn_time(counts, settings),
+    });
+    self.mouse_events.clear();
+    self.tick_start = now;
+    self.last_event = now;
+    let vector = direction * MAX_STICK_TILT;
+    self.x = vector.dx();
+    self.y = vector.dy();
+    self.vector()
+  }
+
+  /// How long the game turns the camera at full tilt to turn by `counts` mouse counts.
+  fn turn_time(counts: f64, settings: &ControllerSettings) -> Duration {
+    let rate = settings.rate();
+    Duration::from_secs_f64(counts * rate.degrees_per_count() / rate.max_turn_rate())
+  }
+
+  /// Emits as much of the residual as a single tick can hold, or recenters the stick and drops the
+  /// rest once less than a stick unit is left.
+  ///
+  /// The payout is not raised to `min_tilt_range`, since that would overshoot and leave a residual
+  /// in the opposite direction.
+  fn pay_out(&mut self) -> Vector {
+    let (x, y) = self.residual;
+    let length = x.hypot(y);
+    let vector = if length < 1.0 {
+      self.residual = Default::default();
+      Vector::default()
+    } else {
+      let scale = (MAX_STICK_TILT / length).min(1.0);
+      let vector = Vector::new(x * scale, y * scale);
+      self.residual = (x - vector.dx(), y - vector.dy());
+      vector
+    };
+    self.x = vector.dx();
+    self.y = vector.dy();
+    self.vector()
+  }
+
+  fn compute_tilt_vector(raw: Vector, tilt: f64, settings: &ControllerSettings) -> Vector {
+    let boost = if raw.dx().abs() > 0.0 && raw.dy().abs() > 0.0 {
+      settings.diagonal_boost()
+    } else {
+      1.0
+    };
+    let angle = raw.dy().atan2(raw.dx());
+    let x = tilt * angle.cos() * boost;
+    let y = tilt * angle.sin() * boost;
+    Vector::new(x, y)
+  }
+
+  /// Moves the stick to `target` as the stick filters let it, but at least to `min_tilt_range`.
+  ///
+  /// A centered stick starts the filters over from the center, so that it eases out of it.
+  fn update_smoothed_position(
+    &mut self,
+    target: Vector,
+    now: Instant,
+    settings: &ControllerSettings,
+  ) {
+    let filters = settings.filters().stick().unwrap_or(&MOTION_SMOOTHING);
+    if self.x == 0.0 && self.y == 0.0 {
+      self.smoothing = FilterChain::default();
+      self.smoothing.apply(Vector::default(), now, filters);
+    }
+    let vector = self.smoothing.apply(target, now, filters);
+    let magnitude = vector.dx().hypot(vector.dy());
+    let min_tilt = settings.min_tilt_range();
+    let vector = if magnitude < min_tilt && magnitude > 0.001 {
+      vector * (min_tilt / magnitude)
+    } else {
+      vector
+    };
+    let vector = Self::clamp_vector(vector, MAX_STICK_TILT);
+    self.x = vector.dx();
+    self.y = vector.dy();
+  }
+
+  fn clamp_vector(v: Vector, max: f64) -> Vector {
+    let length = (v.dx().powi(2) + v.dy().powi(2)).sqrt();
+    if length > max {
+      let scale = max / length;
+      Vector::new(v.dx() * scale, v.dy() * scale)
+    } else {
+      v
+    }
+  }
+
+  fn compute_speed(&mut self, settings: &ControllerSettings) -> f64 {
+    let vector = Vector::sum(&self.mouse_events);
+    let normalized = Self::calculate_normalized_speed(&vector, settings);
+
+    self.motion_history.push(normalized);
+    if self.motion_history.len() > 5 {
+      self.motion_history.remove(0);
+    }
+
+    self.motion_history.iter().copied().sum::<f64>() / self.motion_history.len() as f64
+  }
+
+  fn calculate_normalized_speed(vector: &Vector, settings: &ControllerSettings) -> f64 {
+    let speed = (vector.dx().powi(2) + vector.dy().powi(2)).sqrt() * settings.sensitivity();
+    let min = settings.min_speed_clamp();
+    let max = settings.max_speed_clamp();
+    let clamped = speed.clamp(min, max);
+    (clamped - min) / (max - 1.0)
+  }
+
+  fn motion_from_speed(&self, avg: f64, settings: &ControllerSettings) -> Motion {
+    let new_motion = Motion::from_speed(avg, settings);
+
+    match (self.motion, new_motion) {
+      (Motion::Macro, Motion::Micro) if avg > settings.motion_threshold_micro_macro_recover() => {
+        Motion::Macro
+      }
+      (Motion::Micro, Motion::Macro) if avg < settings.motion_threshold_macro_micro() => {
+        Motion::Micro
+      }
+      (_, updated) => updated,
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use std::time::Duration;
+
+  fn ms(millis: u64) -> Duration {
+    Duration::from_millis(millis)
+  }
+
+  #[test]
+  fn test_flick_moves_the_stick_before_the_tick_ends() {
+    let settings = ControllerSettings::default();
+    let start = Instant::now();
+    let mut mapper = MotionMapper::new(start);
+
+    let first = mapper.map(Vector::new(40.0, 0.0), start + ms(1), &settings);
+    let second = mapper.map(Vector::new(40.0, 0.0), start + ms(2), &settings);
+
+    assert_eq!(first.dx(), 0.0);
+    assert!(second.dx() > 0.0);
+    assert_eq!(second.dy(), 0.0);
+  }
+
+  #[test]
+  fn test_tick_recenters_after_the_idle_timeout() {
+    let settings = ControllerSettings::default();
+    let start = Instant::now();
+    let mut mapper = MotionMapper::new(start);
+
+    mapper.map(Vector::new(40.0, 0.0), start, &settings);
+    mapper.map(Vector::new(40.0, 0.0), start, &settings);
+
+    assert!(mapper.tick(start + ms(10), None, &settings).is_none());
+
+    let vector = mapper.tick(start + settings.mouse_idle_timeout() + ms(1), None, &settings);
+
+    assert!(vector.is_some_and(|vector| vector.dx() == 0.0 && vector.dy() == 0.0));
+    assert!(mapper.tick(start + ms(500), None, &settings).is_none());
+  }
+
+  #[test]
+  fn test_idle_timeout_per_motion_applies_without_walking() {
+    let settings =
+      ControllerSettings::from_toml("test", "[recenter.idle_timeout]\nmicro = 40").unwrap();
+    let start = Instant::now();
+    let mut mapper = MotionMapper::new(start);
+
+    mapper.map(Vector::new(0.5, 0.0), start + ms(1), &settings);
+    mapper.map(Vector::new(0.5, 0.0), start + ms(16), &settings);
+
+    assert_eq!(mapper.motion, Motion::Micro);
+    assert!(mapper.tick(start + ms(16 + 40), None, &settings).is_none());
+
+    let vector = mapper.tick(start + ms(16 + 41), None, &settings);
+
+    assert!(vector.is_some_and(|vector| vector.dx() == 0.0 && vector.dy() == 0.0));
+  }
+
+  #[test]
+  fn test_stick_filters_replace_the_default_smoothing() {
+    let smoothed = ControllerSettings::default();
+    let unfiltered = ControllerSettings::from_toml("test", "[filters]\nstick = []").unwrap();
+    let start = Instant::now();
+
+    let [smoothed, unfiltered] = [smoothed, unfiltered].map(|settings| {
+      let mut mapper = MotionMapper::new(start);
+      mapper.map(Vector::new(3.0, 0.0), start + ms(1), &settings);
+      let vector = mapper.map(Vector::new(3.0, 0.0), start + ms(16), &settings);
+      (vector.dx(), mapper.target(Vector::new(6.0, 0.0), &settings).dx())
+    });
+
+    assert_eq!(smoothed.0, ControllerSettings::default().min_tilt_range());
+    assert!(smoothed.0 < smoothed.1);
+    assert_eq!(unfiltered.0, unfiltered.1);
+  }
+
+  #[test]
+  fn test_single_events_are_committed_when_preserving_distance() {
+    let settings = ControllerSettings::from_toml("test", "preserve_distance = true").unwrap();
+    let start = Instant::now();
+    let mut mapper = MotionMapper::new(start);
+
+    mapper.map(Vector::new(0.5, 0.0), start + ms(1), &settings);
+
+    assert!(mapper.tick(start + ms(8), None, &settings).is_none());
+    assert!(mapper.tick(start + ms(16), None, &settings).is_some_and(|vector| vector.dx() > 0.0));
+  }
+
+  #[test]
+  fn test_residual_is_paid_out_after_the_mouse_stops() {
+    let settings = ControllerSettings::from_toml("test", "preserve_distance = true").unwrap();
+    let start = Instant::now();
+    let mut mapper = MotionMapper::new(start);
+
+    for tick in 1..=3 {
+      mapper.map(Vector::new(2.0, 0.0), start + ms(16 * tick - 8), &settings);
+      mapper.map(Vector::new(2.0, 0.0), start + ms(16 * tick), &settings);
+    }
+
+    let mut payouts = Vec::new();
+    for tick in 4..=12 {
+      if let Some(vector) = mapper.tick(start + ms(16 * tick), None, &settings) {
+        payouts.push(vector.dx());
+      }
+    }
+
+    assert!(payouts.len() >= 2);
+    assert!(payouts[0] > 0.0);
+    assert_eq!(payouts.last(), Some(&0.0));
+  }
+
+  #[test]
+  fn test_preserving_distance_conserves_the_total_tilt() {
+    let settings = ControllerSettings::from_toml("test", "preserve_distance = true").unwrap();
+    let start = Instant::now();
+    let mut mapper = MotionMapper::new(start);
+    let movements = [(2.0, 0.0), (3.0, 1.0), (6.0, 2.0), (1.0, 4.0), (0.5, 0.5), (4.0, -1.0)];
+
+    // Vectors are clamped to a full tilt, so the totals are added up separately.
+    let (mut emitted, mut targets) = ((0.0, 0.0), (0.0, 0.0));
+    for (tick, (dx, dy)) in (1..).zip(movements) {
+      mapper.map(Vector::new(dx, dy), start + ms(16 * tick - 8), &settings);
+      let vector = mapper.map(Vector::new(dx, dy), start + ms(16 * tick), &settings);
+      let target = mapper.target(Vector::new(2.0 * dx, 2.0 * dy), &settings);
+      emitted = (emitted.0 + vector.dx(), emitted.1 + vector.dy());
+      targets = (targets.0 + target.dx(), targets.1 + target.dy());
+    }
+    for tick in 7..=40 {
+      mapper.tick(start + ms(16 * tick), None, &settings);
+      emitted = (emitted.0 + mapper.x, emitted.1 + mapper.y);
+    }
+
+    assert!((emitted.0 - targets.0).abs() < 1.0);
+    assert!((emitted.1 - targets.1).abs() < 1.0);
+    assert_eq!((mapper.x, mapper.y), (0.0, 0.0));
+    assert_eq!(mapper.residual, (0.0, 0.0));
+  }
+
+  #[test]
+  fn test_flick_pulse_holds_full_tilt_for_the_turn() {
+    let settings = ControllerSettings::from_toml("test", "flick = \"pulse\"").unwrap();
+    let start = Instant::now();
+    let mut mapper = MotionMapper::new(start);
+
+    mapper.map(Vector::new(40.0, 0.0), start + ms(1), &settings);
+    let vector = mapper.map(Vector::new(40.0, 0.0), start + ms(2), &settings);
+
+    // 80 counts are 4 degrees, which take 11.1 ms at 360 degrees per second.
+    assert_eq!((vector.dx(), vector.dy()), (MAX_STICK_TILT, 0.0));
+    assert!(mapper.deadline().is_some_and(|end| end > start + ms(13) && end < start + ms(14)));
+    assert!(mapper.tick(start + ms(13), None, &settings).is_none());
+
+    let vector = mapper.tick(start + ms(14), None, &settings);
+
+    assert!(vector.is_some_and(|vector| vector.dx() == 0.0 && vector.dy() == 0.0));
+    assert!(mapper.deadline().is_none());
+  }
+
+  #[test]
+  fn test_flick_pulse_is_extended_while_the_flick_goes_on() {
+    let settings = ControllerSettings::from_toml("test", "flick = \"pulse\"").unwrap();
+    let start = Instant::now();
+    let mut mapper = MotionMapper::new(start);
+
+    mapper.map(Vector::new(40.0, 0.0), start + ms(1), &settings);
+    mapper.map(Vector::new(40.0, 0.0), start + ms(2), &settings);
+    let vector = mapper.map(Vector::new(40.0, 30.0), start + ms(5), &settings);
+
+    assert_eq!((vector.dx(), vector.dy()), (MAX_STICK_TILT, 0.0));
+    assert!(mapper.tick(start + ms(18), None, &settings).is_none());
+    assert!(mapper.tick(start + ms(19), None, &settings).is_some());
+  }
+
+  #[test]
+  fn test_decay_halves_the_tilt_every_half_life() {
+    let settings =
+      ControllerSettings::from_toml("test", "[recenter]\nmode = \"decay\"\nhalf_life = 16")
+        .unwrap();
+    let start = Instant::now();
+    let mut mapper = MotionMapper::new(start);
+    mapper.map(Vector::new(40.0, 0.0), start, &settings);
+    let tilt = mapper.map(Vector::new(40.0, 0.0), start, &settings).dx();
+
+    let vector = mapper.tick(start + ms(64 + 16), None, &settings).unwrap();
+
+    assert!((vector.dx() - tilt / 2.0).abs() < 1e-6);
+
+    let vector = mapper.tick(start + ms(64 + 16 * 20), None, &settings).unwrap();
+
+    assert_eq!((vector.dx(), vector.dy()), (0.0, 0.0));
+  }
+
+  #[test]
+  fn test_inertia_lowers_the_tilt_at_a_constant_rate() {
+    let settings =
+      ControllerSettings::from_toml("test", "[recenter]\nmode = \"inertia\"\ndeceleration = 2.0")
+        .unwrap();
+    let start = Instant::now();
+    let mut mapper = MotionMapper::new(start);
+    mapper.map(Vector::new(40.0, 0.0), start, &settings);
+    let tilt = mapper.map(Vector::new(40.0, 0.0), start, &settings).dx();
+
+    let first = mapper.tick(start + ms(64 + 10), None, &settings).unwrap();
+    let second = mapper.tick(start + ms(64 + 20), None, &settings).unwrap();
+
+    assert!((tilt - first.dx() - 0.02 * MAX_STICK_TILT).abs() < 1e-6);
+    assert!((first.dx() - second.dx() - 0.02 * MAX_STICK_TILT).abs() < 1e-6);
+
+    let vector = mapper.tick(start + ms(64 + 500), None, &settings).unwrap();
+
+    assert_eq!((vector.dx(), vector.dy()), (0.0, 0.0));
+  }
+}
@@ -0,0 +1,192 @@
+use crate::joystick::{direction::Direction, mapper::StickMapper, vector::Vector};
+
+use settings::{ControllerSettings, MAX_STICK_TILT};
+
+use std::time::Instant;
+
+/// Tilts the stick proportionally to the mouse velocity of the last tick, so that the camera turns
+/// as many degrees as the mouse moved counts times `degrees_per_count`.
+///
+/// With `preserve_distance`, the part of a tick that was cut off at full tilt is added to the next
+/// tick instead of being dropped.
+#[derive(Debug, Clone)]
+pub struct RateMapper {
+  x: f64,
+  y: f64,
+  dx: f64,
+  dy: f64,
+  residual: (f64, f64),
+  tick_start: Instant,
+}
+
+impl RateMapper {
+  pub fn new(now: Instant) -> Self {
+    Self {
+      x: Default::default(),
+      y: Default::default(),
+      dx: Default::default(),
+      dy: Default::default(),
+      residual: Default::default(),
+      tick_start: now,
+    }
+  }
+
+  fn vector(&self) -> Vector {
+    Vector::new(self.x, self.y)
+  }
+
+  /// Sets the tilt from the movement since the start of the tick and starts the next one.
+  fn commit(&mut self, now: Instant, settings: &ControllerSettings) -> Vector {
+    let elapsed = now.duration_since(self.tick_start).as_secs_f64();
+    let (dx, dy) = (self.dx, self.dy);
+    self.dx = 0.0;
+    self.dy = 0.0;
+    self.tick_start = now;
+
+    if elapsed <= 0.0 {
+      return self.vector();
+    }
+
+    let rate = settings.rate();
+    let mut x = rate.tilt(dx / elapsed) * MAX_STICK_TILT;
+    let mut y = rate.tilt(dy / elapsed) * MAX_STICK_TILT;
+    if settings.preserve_distance() {
+      x += self.residual.0;
+      y += self.residual.1;
+    }
+    let length = x.hypot(y);
+    let scale = if length > MAX_STICK_TILT {
+      MAX_STICK_TILT / length
+    } else {
+      1.0
+    };
+
+    self.x = x * scale;
+    self.y = y * scale;
+    if settings.preserve_distance() {
+      self.residual = (x - self.x, y - self.y);
+    }
+    self.vector()
+  }
+}
+
+impl StickMapper for RateMapper {
+  fn map(&mut self, delta: Vector, timestamp: Instant, settings: &ControllerSettings) -> Vector {
+    self.dx += delta.dx();
+    self.dy += delta.dy();
+
+    if timestamp.duration_since(self.tick_start) >= settings.tickrate() {
+      return self.commit(timestamp, settings);
+    }
+
+    self.vector()
+  }
+
+  fn tick(
+    &mut self,
+    now: Instant,
+    _: Option<Direction>,
+    settings: &ControllerSettings,
+  ) -> Option<Vector> {
+    if now.duration_since(self.tick_start) < settings.tickrate() {
+      return None;
+    }
+
+    let previous = self.vector();
+    let vector = self.commit(now, settings);
+    (vector.dx() != previous.dx() || vector.dy() != previous.dy()).then_some(vector)
+  }
+
+  fn reset(&mut self, now: Instant) {
+    *self = Self::new(now);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use std::time::Duration;
+
+  fn ms(millis: u64) -> Duration {
+    Duration::from_millis(millis)
+  }
+
+  #[test]
+  fn test_tilt_is_proportional_to_velocity() {
+    let settings = ControllerSettings::from_toml(
+      "test",
+      "[rate]\nmax_turn_rate = 400.0\ndegrees_per_count = 0.1",
+    )
+    .unwrap();
+    let start = Instant::now();
+    let mut mapper = RateMapper::new(start);
+
+    mapper.map(Vector::new(8.0, -4.0), start + ms(8), &settings);
+    let vector = mapper.map(Vector::new(8.0, -4.0), start + ms(16), &settings);
+
+    // 16 counts in 16 ms are 1000 counts or 100 degrees per second, a quarter of the turn rate.
+    assert!((vector.dx() - MAX_STICK_TILT / 4.0).abs() < 1e-6);
+    assert!((vector.dy() + MAX_STICK_TILT / 8.0).abs() < 1e-6);
+  }
+
+  #[test]
+  fn test_fast_movement_saturates_in_the_same_direction() {
+    let settings = ControllerSettings::default();
+    let start = Instant::now();
+    let mut mapper = RateMapper::new(start);
+
+    let vector = mapper.map(Vector::new(3000.0, 4000.0), start + ms(16), &settings);
+
+    assert!((vector.dx().hypot(vector.dy()) - MAX_STICK_TILT).abs() < 1e-6);
+    assert!((vector.dy() / vector.dx() - 4.0 / 3.0).abs() < 1e-9);
+  }
+
+  #[test]
+  fn test_tick_without_movement_recenters() {
+    let settings = ControllerSettings::default();
+    let start = Instant::now();
+    let mut mapper = RateMapper::new(start);
+    mapper.map(Vector::new(50.0, 0.0), start + ms(16), &settings);
+
+    assert!(mapper.tick(start + ms(20), None, &settings).is_none());
+
+    let vector = mapper.tick(start + ms(32), None, &settings).unwrap();
+
+    assert_eq!((vector.dx(), vector.dy()), (0.0, 0.0));
+    assert!(mapper.tick(start + ms(48), None, &settings).is_none());
+  }
+
+  #[test]
+  fn test_cut_off_movement_is_paid_out_when_preserving_distance() {
+    let settings = ControllerSettings::from_toml("test", "preserve_distance = true").unwrap();
+    let start = Instant::now();
+    let mut mapper = RateMapper::new(start);
+
+    // Twice the counts a full tilt turns by in one tick.
+    let vector = mapper.map(Vector::new(2.0 * 7200.0 * 0.016, 0.0), start + ms(16), &settings);
+
+    assert!((vector.dx() - MAX_STICK_TILT).abs() < 1e-6);
+    assert!(mapper.tick(start + ms(32), None, &settings).is_none());
+    assert!((mapper.vector().dx() - MAX_STICK_TILT).abs() < 1e-6);
+
+    let vector = mapper.tick(start + ms(48), None, &settings).unwrap();
+
+    assert!(vector.dx().abs() < 1e-6);
+  }
+
+  #[test]
+  fn test_residual_beyond_a_full_tilt_is_kept() {
+    let settings = ControllerSettings::from_toml("test", "preserve_distance = true").unwrap();
+    let start = Instant::now();
+    let mut mapper = RateMapper::new(start);
+
+    // Three times the counts a full tilt turns by in one tick.
+    mapper.map(Vector::new(3.0 * 7200.0 * 0.016, 0.0), start + ms(16), &settings);
+
+    assert!(mapper.tick(start + ms(32), None, &settings).is_none());
+    assert!(mapper.tick(start + ms(48), None, &settings).is_none());
+    assert!((mapper.vector().dx() - MAX_STICK_TILT).abs() < 1e-6);
+    assert!(mapper.tick(start + ms(64), None, &settings).unwrap().dx().abs() < 1e-6);
+  }
+}
@@ -1,7 +1,11 @@
 mod axis;
 mod direction;
+mod edge;
 mod error;
 mod event;
+mod filter;
+mod mapper;
+mod modifier;
 mod motion;
 mod polarity;
 mod state;
@@ -9,8 +13,12 @@ mod vector;
 
 pub use axis::*;
 pub use direction::*;
+pub use edge::*;
 pub use error::*;
 pub use event::*;
+pub use filter::*;
+pub use mapper::*;
+pub use modifier::*;
 pub use motion::*;
 pub use polarity::*;
 pub use state::*;
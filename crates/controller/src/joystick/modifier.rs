@@ -0,0 +1,54 @@
+/// A bindable action that changes how far the movement keys tilt the left stick.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StickModifier {
+  /// Scales the tilt down to `[left_stick] walk` while held.
+  Walk,
+  /// Selects the level with this number from `[left_stick] levels`, counting from one, until
+  /// another level is selected.
+  Magnitude(usize),
+}
+
+impl std::fmt::Display for StickModifier {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      StickModifier::Walk => write!(f, "walk"),
+      StickModifier::Magnitude(level) => write!(f, "magnitude_{level}"),
+    }
+  }
+}
+
+impl std::str::FromStr for StickModifier {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    let s = s.to_lowercase();
+    if s == "walk" {
+      return Ok(StickModifier::Walk);
+    }
+    s.strip_prefix("magnitude_")
+      .and_then(|level| level.parse::<usize>().ok())
+      .filter(|level| *level > 0)
+      .map(StickModifier::Magnitude)
+      .ok_or_else(|| format!("Invalid StickModifier: {s}"))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_parse_round_trips() {
+    for modifier in [StickModifier::Walk, StickModifier::Magnitude(1), StickModifier::Magnitude(12)]
+    {
+      assert_eq!(modifier.to_string().parse::<StickModifier>(), Ok(modifier));
+    }
+  }
+
+  #[test]
+  fn test_parse_rejects_missing_levels() {
+    assert!("magnitude_0".parse::<StickModifier>().is_err());
+    assert!("magnitude_".parse::<StickModifier>().is_err());
+    assert!("magnitude".parse::<StickModifier>().is_err());
+  }
+}
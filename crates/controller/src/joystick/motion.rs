@@ -1,4 +1,6 @@
-use settings::SETTINGS;
+use settings::ControllerSettings;
+
+use std::time::Duration;
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy, PartialOrd, Ord)]
 pub enum Motion {
@@ -14,25 +16,74 @@ impl Default for Motion {
   }
 }
 
-impl From<f64> for Motion {
-  fn from(avg_speed: f64) -> Self {
-    if avg_speed >= SETTINGS.motion_threshold_macro_flick() {
+impl Motion {
+  pub fn from_speed(avg_speed: f64, settings: &ControllerSettings) -> Self {
+    if avg_speed >= settings.motion_threshold_macro_flick() {
       Motion::Flick
-    } else if avg_speed >= SETTINGS.motion_threshold_micro_macro() {
+    } else if avg_speed >= settings.motion_threshold_micro_macro() {
       Motion::Macro
     } else {
       Motion::Micro
     }
   }
+
+  /// How long the mouse has to be idle during this motion before the stick recenters, as set in
+  /// `[recenter.idle_timeout]` or derived from `mouse_idle_timeout`, which micro motion doubles
+  /// while `walking` with the left stick.
+  pub fn idle_timeout(&self, settings: &ControllerSettings, walking: bool) -> Duration {
+    let timeouts = settings.recenter().idle_timeout();
+    let fallback = settings.mouse_idle_timeout();
+    match self {
+      Motion::Idle => timeouts.idle().unwrap_or(fallback),
+      Motion::Micro => timeouts.micro().unwrap_or(if walking {
+        fallback * 2
+      } else {
+        fallback
+      }),
+      Motion::Macro => timeouts.r#macro().unwrap_or(fallback),
+      Motion::Flick => timeouts.flick().unwrap_or(fallback),
+    }
+  }
 }
 
-impl From<Motion> for std::time::Duration {
-  fn from(value: Motion) -> Self {
-    match value {
-      Motion::Idle => SETTINGS.mouse_idle_timeout(),
-      Motion::Micro => SETTINGS.mouse_idle_timeout() * 2,
-      Motion::Macro => SETTINGS.mouse_idle_timeout(),
-      Motion::Flick => SETTINGS.mouse_idle_timeout(),
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_from_speed_uses_given_thresholds() {
+    let defaults = ControllerSettings::default();
+    let custom = ControllerSettings::from_toml(
+      "test",
+      "motion_threshold_micro_macro = 0.1\nmotion_threshold_macro_flick = 0.2",
+    )
+    .unwrap();
+
+    assert_eq!(Motion::from_speed(0.05, &defaults), Motion::Macro);
+    assert_eq!(Motion::from_speed(0.05, &custom), Motion::Micro);
+    assert_eq!(Motion::from_speed(0.3, &defaults), Motion::Macro);
+    assert_eq!(Motion::from_speed(0.3, &custom), Motion::Flick);
+  }
+
+  #[test]
+  fn test_idle_timeout_doubles_for_micro_while_walking() {
+    let settings = ControllerSettings::from_toml("test", "mouse_idle_timeout = 50").unwrap();
+
+    assert_eq!(Motion::Micro.idle_timeout(&settings, true), Duration::from_millis(100));
+    assert_eq!(Motion::Micro.idle_timeout(&settings, false), Duration::from_millis(50));
+    assert_eq!(Motion::Flick.idle_timeout(&settings, true), Duration::from_millis(50));
+  }
+
+  #[test]
+  fn test_idle_timeout_can_be_set_per_motion() {
+    let settings =
+      ControllerSettings::from_toml("test", "[recenter.idle_timeout]\nmicro = 40\nmacro = 80")
+        .unwrap();
+
+    for walking in [false, true] {
+      assert_eq!(Motion::Micro.idle_timeout(&settings, walking), Duration::from_millis(40));
+      assert_eq!(Motion::Macro.idle_timeout(&settings, walking), Duration::from_millis(80));
+      assert_eq!(Motion::Flick.idle_timeout(&settings, walking), settings.mouse_idle_timeout());
     }
   }
 }
@@ -1,89 +1,324 @@
-use std::time::Instant;
+use std::{
+  sync::Arc,
+  time::{Duration, Instant},
+};
 
 use crate::{
-  joystick::{direction::Direction, motion::Motion, vector::Vector},
-  State,
+  joystick::{
+    direction::Direction,
+    edge::EdgeHold,
+    filter::FilterChain,
+    mapper::{mapper, StickMapper},
+    modifier::StickModifier,
+    vector::Vector,
+  },
+  Clock, State, SystemClock,
+};
+
+use settings::{
+  ControllerSettings, Filter, Gate, HorizontalSocd, RampShape, SettingsHandle, VerticalSocd,
+  MAX_STICK_TILT,
 };
 
-use settings::{LEFT_STICK_SENSITIVITY, MAX_STICK_TILT, SETTINGS};
+/// A movement key, together with when it went down relative to the other movement keys.
+#[derive(Debug, Clone, Copy, Default)]
+struct Key {
+  state: State,
+  pressed: u64,
+}
+
+impl Key {
+  fn is_down(&self) -> bool {
+    matches!(self.state, State::Pressed | State::Held)
+  }
+}
+
+/// Where the left stick is within its ramp, see [`JoyStickState::ramp`].
+#[derive(Debug, Clone, Copy, Default)]
+struct RampState {
+  held: bool,
+  from: f64,
+  progress: f64,
+  level: f64,
+  vector: Vector,
+  updated: Option<Instant>,
+}
+
+/// Which of two opposite keys wins while both are held.
+#[derive(Debug, Clone, Copy)]
+enum Priority {
+  Neutral,
+  Last,
+  First,
+  Negative,
+  Positive,
+}
+
+impl From<HorizontalSocd> for Priority {
+  fn from(socd: HorizontalSocd) -> Self {
+    match socd {
+      HorizontalSocd::Neutral => Self::Neutral,
+      HorizontalSocd::Last => Self::Last,
+      HorizontalSocd::First => Self::First,
+      HorizontalSocd::Left => Self::Negative,
+      HorizontalSocd::Right => Self::Positive,
+    }
+  }
+}
+
+impl From<VerticalSocd> for Priority {
+  fn from(socd: VerticalSocd) -> Self {
+    match socd {
+      VerticalSocd::Neutral => Self::Neutral,
+      VerticalSocd::Last => Self::Last,
+      VerticalSocd::First => Self::First,
+      VerticalSocd::Down => Self::Negative,
+      VerticalSocd::Up => Self::Positive,
+    }
+  }
+}
 
-#[derive(Debug, Clone)]
+impl Priority {
+  /// Whether the `negative` and the `positive` key count as held.
+  fn resolve(self, negative: Key, positive: Key) -> (bool, bool) {
+    if !(negative.is_down() && positive.is_down()) {
+      return (negative.is_down(), positive.is_down());
+    }
+    let positive_last = positive.pressed > negative.pressed;
+    match self {
+      Priority::Neutral => (false, false),
+      Priority::Last => (!positive_last, positive_last),
+      Priority::First => (positive_last, !positive_last),
+      Priority::Negative => (true, false),
+      Priority::Positive => (false, true),
+    }
+  }
+}
+
+#[derive(Debug)]
 pub struct JoyStickState {
+  settings: SettingsHandle,
+  clock: Arc<dyn Clock>,
+  mapper: Box<dyn StickMapper>,
+  mouse_x: FilterChain,
+  mouse_y: FilterChain,
+  stick_filters: FilterChain,
+  raw: Vector,
+  edge: EdgeHold,
   x: f64,
   y: f64,
-  up: State,
-  down: State,
-  left: State,
-  right: State,
+  up: Key,
+  down: Key,
+  left: Key,
+  right: Key,
+  presses: u64,
+  ramp: RampState,
+  walk: bool,
+  level: Option<usize>,
   direction: Option<Direction>,
-  motion: Motion,
-  motion_history: Vec<f64>,
-  angle: Option<f64>,
-  last_event: Instant,
-  tick_start: Instant,
-  mouse_events: Vec<Vector>,
 }
 
-impl Default for JoyStickState {
-  fn default() -> Self {
+impl JoyStickState {
+  /// Creates a stick that uses the mapper selected in `settings` and the wall clock.
+  pub fn new(settings: SettingsHandle) -> Self {
+    Self::with_clock(settings, Arc::new(SystemClock))
+  }
+
+  /// Creates a stick that uses the mapper selected in `settings` and reads the time from `clock`.
+  pub fn with_clock(settings: SettingsHandle, clock: Arc<dyn Clock>) -> Self {
+    let mapper = mapper(settings.load().mapper(), clock.now());
     Self {
+      settings,
+      clock,
+      mapper,
+      mouse_x: FilterChain::default().with_settle(0.0),
+      mouse_y: FilterChain::default().with_settle(0.0),
+      stick_filters: Default::default(),
+      raw: Default::default(),
+      edge: Default::default(),
       x: Default::default(),
       y: Default::default(),
       up: Default::default(),
       down: Default::default(),
       left: Default::default(),
       right: Default::default(),
+      presses: Default::default(),
+      ramp: Default::default(),
+      walk: Default::default(),
+      level: Default::default(),
       direction: Default::default(),
-      motion: Default::default(),
-      motion_history: Default::default(),
-      angle: Default::default(),
-      last_event: Instant::now(),
-      tick_start: Instant::now(),
-      mouse_events: Default::default(),
     }
   }
+
+  /// Turns mouse movement into positions with `mapper` instead of the one from the settings.
+  pub fn with_mapper(mut self, mapper: Box<dyn StickMapper>) -> Self {
+    self.mapper = mapper;
+    self
+  }
 }
 
 impl JoyStickState {
-  pub fn tilt(&mut self, vector: Vector) -> Vector {
-    self.last_event = Instant::now();
-    self.x += vector.dx() * LEFT_STICK_SENSITIVITY;
-    self.y += vector.dy() * LEFT_STICK_SENSITIVITY;
-    self.clamp_position(MAX_STICK_TILT);
+  /// Tilts the stick towards `direction` as far as the left stick settings allow.
+  pub fn tilt(&mut self, direction: Direction) -> Vector {
+    let settings = self.settings.load();
+    let left_stick = settings.left_stick();
+    let unit = Vector::from(direction);
+    let scale = match left_stick.gate() {
+      Gate::Circular => (unit.dx().powi(2) + unit.dy().powi(2)).sqrt().recip(),
+      Gate::Square => 1.0,
+    };
+    let boost = if direction == Direction::North {
+      left_stick.forward_boost()
+    } else {
+      1.0
+    };
+    let vector = unit * (scale * self.magnitude(direction) * boost * MAX_STICK_TILT);
+    let vector = vector * self.speed(&settings);
+    self.x = vector.dx();
+    self.y = vector.dy();
     self.vector()
   }
 
-  pub fn micro(&mut self, vector: Vector) -> Vector {
-    let now = Instant::now();
-    self.mouse_events.push(vector);
-
-    if self.mouse_events.len() >= 2 {
-      let speed = self.compute_speed();
-      self.motion = self.motion_from_speed(speed);
-
-      match self.motion {
-        Motion::Flick => {
-          return self.commit(now);
-        }
-        Motion::Micro => {
-          self.last_event = now;
-        }
-        _ => (),
-      }
+  /// Tilts the stick towards the held direction, growing the tilt while keys are held and
+  /// shrinking it after they are released, as configured in `[left_stick.ramp]`.
+  ///
+  /// Returns `None` once the stick is back in the center.
+  pub fn ramp(&mut self) -> Option<Vector> {
+    let settings = self.settings.load();
+    let ramp = settings.left_stick().ramp();
+    let direction = self.direction;
+
+    if ramp.shape() == RampShape::Off {
+      self.ramp = Default::default();
+      return direction.map(|direction| self.tilt(direction));
+    }
+
+    let now = self.clock.now();
+    let held = direction.is_some();
+    let duration = if held {
+      ramp.attack()
+    } else {
+      ramp.release()
+    };
+
+    if held != self.ramp.held {
+      self.ramp.held = held;
+      self.ramp.from = if held {
+        self.ramp.level.max(ramp.start())
+      } else {
+        self.ramp.level
+      };
+      self.ramp.progress = 0.0;
+    } else if let Some(updated) = self.ramp.updated {
+      let elapsed = now.saturating_duration_since(updated);
+      self.ramp.progress += elapsed.as_secs_f64() / duration.as_secs_f64();
+    }
+    if duration.is_zero() {
+      self.ramp.progress = 1.0;
     }
+    self.ramp.progress = self.ramp.progress.min(1.0);
+    self.ramp.updated = Some(now);
 
-    if now.duration_since(self.tick_start) >= SETTINGS.tickrate() {
-      return self.commit(now);
+    let to = if held {
+      1.0
+    } else {
+      0.0
+    };
+    let from = self.ramp.from;
+    self.ramp.level = from + (to - from) * ramp.shape().apply(self.ramp.progress);
+
+    if let Some(direction) = direction {
+      self.ramp.vector = self.tilt(direction);
+    }
+    if self.ramp.level <= 0.0 {
+      self.recenter();
+      return None;
     }
 
-    self.vector()
+    let vector = self.ramp.vector * self.ramp.level;
+    self.x = vector.dx();
+    self.y = vector.dy();
+    Some(vector)
   }
 
+  /// Moves the stick according to a mouse movement, as decided by the mapper, unless it is held
+  /// at the edge.
+  ///
+  /// The movement passes the mouse filters before it reaches the mapper, and the position passes
+  /// the stick filters whenever the mapper changes it, unless the mapper runs them itself.
+  ///
+  /// Mice report each axis in an event of its own, so each axis has its own mouse filters, which
+  /// only see the events that move along it.
+  pub fn micro(&mut self, vector: Vector) -> Vector {
+    let settings = self.settings.load();
+    let now = self.clock.now();
+    let filters = settings.filters().mouse();
+    let dx = match vector.dx() {
+      0.0 => 0.0,
+      dx => self.mouse_x.apply(Vector::new(dx, 0.0), now, filters).dx(),
+    };
+    let dy = match vector.dy() {
+      0.0 => 0.0,
+      dy => self.mouse_y.apply(Vector::new(0.0, dy), now, filters).dy(),
+    };
+    let (dx, dy) = settings.transform().mouse(dx, dy);
+    let delta = Vector::new(dx, dy);
+    let raw = self.mapper.map(delta, now, &settings);
+    let vector = if raw == self.raw {
+      self.stick_filters.output()
+    } else {
+      self.raw = raw;
+      self.stick_filters.apply(raw, now, self.stick_filters(&settings))
+    };
+    let vector = self.edge.hold(delta, vector, now, settings.edge());
+    let vector = Self::calibrate(vector, &settings);
+    self.x = vector.dx();
+    self.y = vector.dy();
+    vector
+  }
+
+  /// Lets the mapper react to the passing of time, e.g. by recentering an idle stick.
+  ///
+  /// Returns the new position if it changed. A stick that is held at the edge stays where it is.
+  /// The stick filters are stepped every tick, so that they keep settling while the mapper holds
+  /// its position.
+  pub fn tick(&mut self, left_stick_direction: Option<Direction>) -> Option<Vector> {
+    let settings = self.settings.load();
+    let now = self.clock.now();
+    let vector = self.mapper.tick(now, left_stick_direction, &settings);
+    if self.edge.is_latched() {
+      return None;
+    }
+    let filters = self.stick_filters(&settings);
+    let vector = if filters.is_empty() {
+      vector?
+    } else {
+      self.raw = vector.unwrap_or(self.raw);
+      let vector = self.stick_filters.apply(self.raw, now, filters);
+      if Self::calibrate(vector, &settings) == self.vector() {
+        return None;
+      }
+      vector
+    };
+    let vector = Self::calibrate(vector, &settings);
+    self.x = vector.dx();
+    self.y = vector.dy();
+    Some(vector)
+  }
+
+  /// How long until the mapper wants to be ticked again, if that is before the next regular tick.
+  pub fn until_deadline(&self) -> Option<Duration> {
+    let deadline = self.mapper.deadline()?;
+    Some(deadline.saturating_duration_since(self.clock.now()))
+  }
+
+  /// Derives the direction from the held movement keys, resolving opposite keys as configured
+  /// in `[left_stick.socd]`.
   pub fn update_direction(&mut self) {
-    let up = matches!(self.up, State::Pressed | State::Held);
-    let down = matches!(self.down, State::Pressed | State::Held);
-    let left = matches!(self.left, State::Pressed | State::Held);
-    let right = matches!(self.right, State::Pressed | State::Held);
+    let settings = self.settings.load();
+    let socd = settings.left_stick().socd();
+    let (left, right) = Priority::from(socd.horizontal()).resolve(self.left, self.right);
+    let (down, up) = Priority::from(socd.vertical()).resolve(self.down, self.up);
 
     self.direction = match (up, down, left, right) {
       (true, false, true, false) => Some(Direction::NorthWest),
@@ -103,24 +338,58 @@ impl JoyStickState {
     self.y = 0.0;
   }
 
+  /// Releases every direction and recenters the stick, with a fresh instance of the mapper that
+  /// is selected in the current settings.
+  ///
+  /// The selected magnitude level is kept, since it is not tied to a held key.
   pub fn reset(&mut self) {
-    *self = Self::default();
+    let level = self.level;
+    *self = Self::with_clock(self.settings.clone(), Arc::clone(&self.clock));
+    self.level = level;
+  }
+
+  pub fn settings(&self) -> &SettingsHandle {
+    &self.settings
   }
 
   pub fn set_up(&mut self, up: State) {
-    self.up = up;
+    self.up = self.press(self.up, up);
   }
 
   pub fn set_down(&mut self, down: State) {
-    self.down = down;
+    self.down = self.press(self.down, down);
   }
 
   pub fn set_left(&mut self, left: State) {
-    self.left = left;
+    self.left = self.press(self.left, left);
   }
 
   pub fn set_right(&mut self, right: State) {
-    self.right = right;
+    self.right = self.press(self.right, right);
+  }
+
+  /// Holds or releases `walk`, or selects a magnitude level once its key goes down.
+  pub fn set_modifier(&mut self, modifier: StickModifier, state: State) {
+    match modifier {
+      StickModifier::Walk => self.walk = state != State::Released,
+      StickModifier::Magnitude(level) if state == State::Pressed => self.level = Some(level - 1),
+      StickModifier::Magnitude(_) => {}
+    }
+  }
+
+  /// Moves `key` into `state`, remembering the order in which keys went down.
+  fn press(&mut self, key: Key, state: State) -> Key {
+    let mut key = Key {
+      state,
+      ..key
+    };
+    if key.is_down() && key.pressed == 0 {
+      self.presses += 1;
+      key.pressed = self.presses;
+    } else if !key.is_down() {
+      key.pressed = 0;
+    }
+    key
   }
 
   pub fn direction(&self) -> Option<Direction> {
@@ -131,163 +400,385 @@ impl JoyStickState {
     Vector::new(self.x, self.y)
   }
 
-  pub fn is_idle(&self, left_stick_direction: Option<Direction>) -> bool {
-    let now = Instant::now();
-    let timeout = if self.motion == Motion::Micro && left_stick_direction.is_some() {
-      SETTINGS.mouse_idle_timeout() * 2
-    } else {
-      SETTINGS.mouse_idle_timeout()
-    };
-    now.duration_since(self.last_event()) > timeout && !self.is_centered()
-  }
-
   pub fn is_centered(&self) -> bool {
     self.x == 0.0 && self.y == 0.0
   }
 
-  pub fn handle_idle(&mut self, left_stick_direction: Option<Direction>) -> bool {
-    if self.is_idle(left_stick_direction) {
-      self.reset();
-      return true;
+  /// The stick filters left to run on the positions of the mapper, none by default.
+  fn stick_filters<'a>(&self, settings: &'a ControllerSettings) -> &'a [Filter] {
+    if self.mapper.filters_positions() {
+      &[]
+    } else {
+      settings.filters().stick().unwrap_or_default()
     }
-    false
   }
 
-  fn last_event(&self) -> Instant {
-    self.last_event
-  }
-
-  fn commit(&mut self, now: Instant) -> Vector {
-    self.tick_start = now;
-    self.last_event = now;
-    if self.mouse_events.len() < 2 {
-      return self.vector();
+  /// Scales a position of the mapper so that the game turns the camera proportionally to it.
+  fn calibrate(vector: Vector, settings: &ControllerSettings) -> Vector {
+    let length = vector.dx().hypot(vector.dy()) / MAX_STICK_TILT;
+    if length == 0.0 {
+      return vector;
     }
-    let vector = Vector::sum(&self.mouse_events);
-    let tilt = if self.motion == Motion::Flick {
-      SETTINGS.max_tilt_range()
-    } else {
-      let normalized_speed = self.calculate_normalized_speed(&vector);
-      let min = SETTINGS.min_tilt_range();
-      let max = SETTINGS.max_tilt_range();
-      min + (max - min) * normalized_speed
-    };
-    let vector = self.compute_tilt_vector(vector, tilt);
-    self.update_smoothed_position(vector, SETTINGS.blend());
-    self.mouse_events.clear();
-    self.vector()
+    vector * (settings.calibration().apply(length) / length)
   }
 
-  fn compute_tilt_vector(&self, raw: Vector, tilt: f64) -> Vector {
-    let boost = if raw.dx().abs() > 0.0 && raw.dy().abs() > 0.0 {
-      SETTINGS.diagonal_boost()
+  /// The share of the tilt that the selected magnitude level and the `walk` key leave.
+  ///
+  /// Levels that are not configured count as a full tilt.
+  fn speed(&self, settings: &ControllerSettings) -> f64 {
+    let left_stick = settings.left_stick();
+    let level = self.level.and_then(|level| left_stick.levels().get(level).copied());
+    let walk = if self.walk {
+      left_stick.walk()
     } else {
       1.0
     };
-    let angle = raw.dy().atan2(raw.dx());
-    let x = tilt * angle.cos() * boost;
-    let y = tilt * angle.sin() * boost;
-    Vector::new(x, y)
-  }
-
-  fn update_smoothed_position(&mut self, target: Vector, blend: f64) {
-    let prev = self.vector();
-    let min_tilt = SETTINGS.min_tilt_range();
-    let x = (1.0 - blend) * prev.dx() + blend * target.dx();
-    let y = (1.0 - blend) * prev.dy() + blend * target.dy();
-    let vector = Vector::new(x, y);
-    let magnitude = self.smooth_magnitude(prev, vector);
-    let adjusted_mag = if magnitude < min_tilt && magnitude > 0.001 {
-      min_tilt
-    } else {
-      magnitude
-    };
-    let angle = self.update_angle(vector);
-    let final_x = adjusted_mag * angle.to_radians().cos();
-    let final_y = adjusted_mag * angle.to_radians().sin();
-    let vector = Vector::new(final_x, final_y);
-    let vector = self.clamp_vector(vector, MAX_STICK_TILT);
-    self.x = vector.dx();
-    self.y = vector.dy();
+    level.unwrap_or(1.0) * walk
   }
 
-  fn clamp_vector(&self, v: Vector, max: f64) -> Vector {
-    let length = (v.dx().powi(2) + v.dy().powi(2)).sqrt();
-    if length > max {
-      let scale = max / length;
-      Vector::new(v.dx() * scale, v.dy() * scale)
-    } else {
-      v
+  fn magnitude(&self, direction: Direction) -> f64 {
+    let settings = self.settings.load();
+    let magnitude = settings.left_stick().magnitude();
+    match direction {
+      Direction::North => magnitude.north(),
+      Direction::NorthEast => magnitude.north_east(),
+      Direction::East => magnitude.east(),
+      Direction::SouthEast => magnitude.south_east(),
+      Direction::South => magnitude.south(),
+      Direction::SouthWest => magnitude.south_west(),
+      Direction::West => magnitude.west(),
+      Direction::NorthWest => magnitude.north_west(),
     }
   }
+}
 
-  fn clamp_position(&mut self, max: f64) {
-    let mag = (self.x.powi(2) + self.y.powi(2)).sqrt();
-    if mag > max {
-      let scale = max / mag;
-      self.x = (self.x * scale).round();
-      self.y = (self.y * scale).round();
-    }
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use crate::ManualClock;
+
+  use std::time::{Duration, Instant};
+
+  fn settings(toml: &str) -> SettingsHandle {
+    SettingsHandle::new(ControllerSettings::from_toml("test", toml).unwrap())
+  }
+
+  fn stick(toml: &str) -> JoyStickState {
+    JoyStickState::new(settings(toml))
+  }
+
+  fn manual(toml: &str) -> (JoyStickState, Arc<ManualClock>) {
+    let clock = Arc::new(ManualClock::default());
+    (JoyStickState::with_clock(settings(toml), clock.clone()), clock)
   }
 
-  fn compute_speed(&mut self) -> f64 {
-    let vector = Vector::sum(&self.mouse_events);
-    let normalized = self.calculate_normalized_speed(&vector);
+  fn ms(millis: u64) -> Duration {
+    Duration::from_millis(millis)
+  }
+
+  #[derive(Debug)]
+  struct Echo;
+
+  impl StickMapper for Echo {
+    fn map(&mut self, delta: Vector, _: Instant, _: &ControllerSettings) -> Vector {
+      delta * 100.0
+    }
 
-    self.motion_history.push(normalized);
-    if self.motion_history.len() > 5 {
-      self.motion_history.remove(0);
+    fn tick(&mut self, _: Instant, _: Option<Direction>, _: &ControllerSettings) -> Option<Vector> {
+      Some(Vector::default())
     }
 
-    self.motion_history.iter().copied().sum::<f64>() / self.motion_history.len() as f64
+    fn reset(&mut self, _: Instant) {}
   }
 
-  fn calculate_normalized_speed(&self, vector: &Vector) -> f64 {
-    let speed = (vector.dx().powi(2) + vector.dy().powi(2)).sqrt() * SETTINGS.sensitivity();
-    let min = SETTINGS.min_speed_clamp();
-    let max = SETTINGS.max_speed_clamp();
-    let clamped = speed.clamp(min, max);
-    (clamped - min) / (max - 1.0)
+  #[test]
+  fn test_micro_feeds_transformed_movement_to_the_mapper() {
+    let mut stick = stick("[transform]\ninvert_right_y = true").with_mapper(Box::new(Echo));
+
+    let vector = stick.micro(Vector::new(1.0, 2.0));
+
+    assert_eq!((vector.dx(), vector.dy()), (100.0, -200.0));
+    assert!(!stick.is_centered());
+    assert!(stick.tick(None).is_some());
+    assert!(stick.is_centered());
   }
 
-  fn motion_from_speed(&self, avg: f64) -> Motion {
-    let new_motion = Motion::from(avg);
+  #[test]
+  fn test_mapper_output_is_calibrated() {
+    let mut stick =
+      stick("[calibration]\npoints = [[0.0, 0.2], [1.0, 1.0]]").with_mapper(Box::new(Echo));
 
-    match (self.motion, new_motion) {
-      (Motion::Macro, Motion::Micro) if avg > SETTINGS.motion_threshold_micro_macro_recover() => {
-        Motion::Macro
-      }
-      (Motion::Micro, Motion::Macro) if avg < SETTINGS.motion_threshold_macro_micro() => {
-        Motion::Micro
-      }
-      (_, updated) => updated,
-    }
+    let vector = stick.micro(Vector::new(0.0, -MAX_STICK_TILT / 200.0));
+
+    assert!((vector.dy() + 0.6 * MAX_STICK_TILT).abs() < 1e-6);
+    assert_eq!(vector.dx(), 0.0);
   }
 
-  fn smooth_magnitude(&self, prev: Vector, new: Vector) -> f64 {
-    let mag = (new.dx().powi(2) + new.dy().powi(2)).sqrt();
-    let prev_mag = (prev.dx().powi(2) + prev.dy().powi(2)).sqrt();
-    let delta = (mag - prev_mag).abs();
-    if delta < SETTINGS.speed_stabilize_threshold() {
-      prev_mag
-    } else {
-      mag
+  #[test]
+  fn test_mouse_filters_run_before_the_mapper() {
+    let mut stick =
+      stick("[[filters.mouse]]\nkind = \"median\"\nwindow = 3").with_mapper(Box::new(Echo));
+
+    stick.micro(Vector::new(1.0, 0.0));
+    stick.micro(Vector::new(1.0, 0.0));
+
+    assert_eq!(stick.micro(Vector::new(100.0, 0.0)).dx(), 100.0);
+  }
+
+  #[test]
+  fn test_mouse_filters_see_each_axis_on_its_own() {
+    let mut stick =
+      stick("[[filters.mouse]]\nkind = \"median\"\nwindow = 3").with_mapper(Box::new(Echo));
+
+    for _ in 0..2 {
+      stick.micro(Vector::new(3.0, 0.0));
+      stick.micro(Vector::new(0.0, 2.0));
     }
+
+    assert_eq!(stick.micro(Vector::new(3.0, 0.0)).dx(), 300.0);
+    assert_eq!(stick.micro(Vector::new(0.0, 2.0)).dy(), 200.0);
   }
 
-  fn update_angle(&mut self, v: Vector) -> f64 {
-    let angle = v.dy().atan2(v.dx()).to_degrees();
-    self.angle = match self.angle {
-      Some(prev) => {
-        let delta = ((angle - prev + 180.0) % 360.0) - 180.0;
-        if delta.abs() < SETTINGS.angle_delta_limit() {
-          Some(prev)
-        } else {
-          Some(angle)
-        }
-      }
-      None => Some(angle),
-    };
-    self.angle.unwrap()
+  #[test]
+  fn test_stick_filters_keep_settling_every_tick() {
+    let mut stick =
+      stick("[[filters.stick]]\nkind = \"ema\"\nalpha = 0.5").with_mapper(Box::new(Echo));
+
+    stick.micro(Vector::new(1.0, 0.0));
+
+    assert_eq!(stick.micro(Vector::new(3.0, 0.0)).dx(), 200.0);
+    assert_eq!(stick.tick(None).map(|vector| vector.dx()), Some(100.0));
+    assert_eq!(stick.tick(None).map(|vector| vector.dx()), Some(50.0));
+
+    while stick.tick(None).is_some() {}
+
+    assert!(stick.is_centered());
+  }
+
+  #[test]
+  fn test_stick_is_held_at_the_edge_until_the_mouse_moves_back() {
+    let mut stick = stick("[edge]\ndistance = 50.0\nwindow = 100").with_mapper(Box::new(Echo));
+
+    stick.micro(Vector::new(30.0, 0.0));
+    stick.micro(Vector::new(30.0, 0.0));
+
+    assert!(stick.tick(None).is_none());
+    assert_eq!(stick.micro(Vector::new(0.0, 5.0)).dx(), 3000.0);
+
+    let vector = stick.micro(Vector::new(-1.0, 0.0));
+
+    assert_eq!((vector.dx(), vector.dy()), (-100.0, 0.0));
+    assert!(stick.tick(None).is_some());
+    assert!(stick.is_centered());
+  }
+
+  #[test]
+  fn test_slow_travel_does_not_reach_the_edge() {
+    let clock = Arc::new(ManualClock::default());
+    let mut stick =
+      JoyStickState::with_clock(settings("[edge]\ndistance = 50.0\nwindow = 100"), clock.clone())
+        .with_mapper(Box::new(Echo));
+
+    stick.micro(Vector::new(30.0, 0.0));
+    clock.advance(ms(101));
+    stick.micro(Vector::new(30.0, 0.0));
+
+    assert!(stick.tick(None).is_some());
+    assert!(stick.is_centered());
+  }
+
+  #[test]
+  fn test_slow_movement_is_committed_once_per_tick() {
+    let (mut stick, clock) = manual("");
+    let min_tilt = stick.settings().load().min_tilt_range();
+
+    stick.micro(Vector::new(0.5, 0.0));
+    stick.micro(Vector::new(0.5, 0.0));
+
+    assert!(stick.is_centered());
+
+    clock.advance(ms(16));
+
+    assert_eq!(stick.micro(Vector::new(0.5, 0.0)).dx(), min_tilt);
+  }
+
+  #[test]
+  fn test_idle_stick_is_recentered_after_the_timeout() {
+    let (mut stick, clock) = manual("mouse_idle_timeout = 64");
+    stick.micro(Vector::new(40.0, 0.0));
+    stick.micro(Vector::new(40.0, 0.0));
+
+    clock.advance(ms(64));
+
+    assert!(stick.tick(None).is_none());
+    assert!(!stick.is_centered());
+
+    clock.advance(ms(1));
+
+    assert!(stick.tick(None).is_some());
+    assert!(stick.is_centered());
+    assert!(stick.tick(None).is_none());
+  }
+
+  #[test]
+  fn test_micro_motion_waits_longer_while_walking() {
+    let (mut stick, clock) = manual("mouse_idle_timeout = 64");
+    stick.micro(Vector::new(0.5, 0.0));
+    stick.micro(Vector::new(0.5, 0.0));
+    clock.advance(ms(16));
+    stick.micro(Vector::new(0.5, 0.0));
+
+    clock.advance(ms(65));
+
+    assert!(stick.tick(Some(Direction::North)).is_none());
+
+    clock.advance(ms(64));
+
+    assert!(stick.tick(Some(Direction::North)).is_some());
+  }
+
+  #[test]
+  fn test_opposite_keys_cancel_out_by_default() {
+    let mut stick = stick("");
+    stick.set_right(State::Pressed);
+    stick.set_left(State::Pressed);
+    stick.update_direction();
+
+    assert_eq!(stick.direction(), None);
+  }
+
+  #[test]
+  fn test_last_input_wins_and_falls_back_on_release() {
+    let mut stick = stick("[left_stick.socd]\nhorizontal = \"last\"");
+    stick.set_right(State::Pressed);
+    stick.set_right(State::Held);
+    stick.set_left(State::Pressed);
+    stick.update_direction();
+
+    assert_eq!(stick.direction(), Some(Direction::West));
+
+    stick.set_left(State::Released);
+    stick.update_direction();
+
+    assert_eq!(stick.direction(), Some(Direction::East));
+  }
+
+  #[test]
+  fn test_first_input_and_fixed_priority() {
+    let mut stick = stick("[left_stick.socd]\nhorizontal = \"first\"\nvertical = \"up\"");
+    stick.set_down(State::Pressed);
+    stick.set_up(State::Pressed);
+    stick.set_right(State::Pressed);
+    stick.set_left(State::Pressed);
+    stick.update_direction();
+
+    assert_eq!(stick.direction(), Some(Direction::NorthEast));
+  }
+
+  #[test]
+  fn test_ramp_grows_while_held_and_eases_back_on_release() {
+    let (mut stick, clock) =
+      manual("[left_stick.ramp]\nshape = \"linear\"\nstart = 0.5\nattack = 100\nrelease = 50");
+    stick.set_right(State::Pressed);
+    stick.update_direction();
+
+    assert_eq!(stick.ramp().unwrap().dx(), MAX_STICK_TILT * 0.5);
+
+    clock.advance(ms(50));
+
+    assert_eq!(stick.ramp().unwrap().dx(), MAX_STICK_TILT * 0.75);
+
+    clock.advance(ms(60));
+
+    assert_eq!(stick.ramp().unwrap().dx(), MAX_STICK_TILT);
+
+    stick.set_right(State::Released);
+    stick.update_direction();
+    stick.ramp();
+    clock.advance(ms(25));
+
+    assert_eq!(stick.ramp().unwrap().dx(), MAX_STICK_TILT * 0.5);
+
+    clock.advance(ms(25));
+
+    assert!(stick.ramp().is_none());
+    assert!(stick.is_centered());
+  }
+
+  #[test]
+  fn test_ease_in_ramp_starts_slowly() {
+    let (mut stick, clock) =
+      manual("[left_stick.ramp]\nshape = \"ease_in\"\nstart = 0.0\nattack = 100");
+    stick.set_left(State::Pressed);
+    stick.update_direction();
+
+    assert!(stick.ramp().is_none());
+
+    clock.advance(ms(50));
+
+    assert_eq!(stick.ramp().unwrap().dx(), -MAX_STICK_TILT * 0.25);
+  }
+
+  #[test]
+  fn test_walk_scales_the_tilt_while_held() {
+    let mut stick = stick("[left_stick]\nwalk = 0.25");
+    stick.set_modifier(StickModifier::Walk, State::Pressed);
+
+    assert_eq!(stick.tilt(Direction::North).dy(), MAX_STICK_TILT * 0.25);
+
+    stick.set_modifier(StickModifier::Walk, State::Released);
+
+    assert_eq!(stick.tilt(Direction::North).dy(), MAX_STICK_TILT);
+  }
+
+  #[test]
+  fn test_magnitude_levels_persist_and_combine_with_walk() {
+    let mut stick = stick("[left_stick]\nwalk = 0.5\nlevels = [0.2, 0.8]");
+    stick.set_modifier(StickModifier::Magnitude(2), State::Pressed);
+    stick.set_modifier(StickModifier::Magnitude(2), State::Released);
+
+    assert_eq!(stick.tilt(Direction::East).dx(), MAX_STICK_TILT * 0.8);
+
+    stick.set_modifier(StickModifier::Walk, State::Pressed);
+
+    assert_eq!(stick.tilt(Direction::East).dx(), MAX_STICK_TILT * 0.4);
+
+    stick.reset();
+
+    assert_eq!(stick.tilt(Direction::East).dx(), MAX_STICK_TILT * 0.8);
+
+    stick.set_modifier(StickModifier::Magnitude(3), State::Pressed);
+
+    assert_eq!(stick.tilt(Direction::East).dx(), MAX_STICK_TILT);
+  }
+
+  #[test]
+  fn test_tilt_circular_gate_normalizes_diagonals() {
+    let vector = stick("").tilt(Direction::NorthEast);
+
+    assert!((vector.dx() - MAX_STICK_TILT / 2f64.sqrt()).abs() < 1e-6);
+    assert!((vector.dy() - MAX_STICK_TILT / 2f64.sqrt()).abs() < 1e-6);
+  }
+
+  #[test]
+  fn test_tilt_square_gate_keeps_corners() {
+    let vector = stick("[left_stick]\ngate = \"square\"").tilt(Direction::SouthWest);
+
+    assert_eq!(vector.dx(), -MAX_STICK_TILT);
+    assert_eq!(vector.dy(), -MAX_STICK_TILT);
+  }
+
+  #[test]
+  fn test_tilt_forward_boost_is_limited_to_full_tilt() {
+    let mut slow = stick("[left_stick.magnitude]\nnorth = 0.4\nsouth = 0.4");
+
+    assert_eq!(slow.tilt(Direction::North).dy(), MAX_STICK_TILT * 0.8);
+    assert_eq!(slow.tilt(Direction::South).dy(), -MAX_STICK_TILT * 0.4);
+
+    let mut boosted =
+      stick("[left_stick]\nforward_boost = 3.0\n[left_stick.magnitude]\nnorth = 0.5");
+
+    assert_eq!(boosted.tilt(Direction::North).dy(), MAX_STICK_TILT);
   }
 }
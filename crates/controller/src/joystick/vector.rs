@@ -5,7 +5,7 @@ use crate::{
 
 use settings::{MAX_STICK_TILT, MIN_STICK_TILT};
 
-#[derive(Default, Debug, Copy, Clone)]
+#[derive(Default, Debug, Copy, Clone, PartialEq)]
 pub struct Vector {
   dx: f64,
   dy: f64,
@@ -1,23 +1,31 @@
 mod button;
+mod clock;
 mod error;
 mod event;
 mod joystick;
+mod trigger;
 
 pub use button::*;
+pub use clock::*;
 pub use error::*;
 pub use event::*;
 pub use joystick::*;
+pub use trigger::*;
 
-use settings::SETTINGS;
+use settings::{ConfigWatcher, SettingsHandle};
 
-use std::sync::{Arc, Mutex};
+use std::{
+  path::Path,
+  sync::{Arc, Mutex},
+  time::Duration,
+};
 
 pub trait PlatformControllerManager: VirtualController + Sized + 'static {
   type Ops: PlatformControllerOps;
 
-  fn run() -> Result<(), Box<dyn std::error::Error>> {
+  fn run(settings: SettingsHandle) -> Result<(), Box<dyn std::error::Error>> {
     println!("Starting JoyMouse 🎮🐭");
-    let controller = Arc::new(Mutex::new(Self::try_create()?));
+    let controller = Arc::new(Mutex::new(Self::try_create(settings)?));
 
     let signal_handler = Arc::clone(&controller);
     let _ = ctrlc::set_handler(move || {
@@ -40,13 +48,52 @@ pub trait PlatformControllerManager: VirtualController + Sized + 'static {
     let right_stick = Arc::clone(&controller);
     std::thread::spawn(move || Self::monitor_right_stick(right_stick));
 
+    let config = Arc::clone(&controller);
+    std::thread::spawn(move || Self::monitor_config(config));
+
     println!("Started JoyMouse 🎮🐭");
 
     io.join().unwrap();
     Ok(())
   }
 
-  fn try_create() -> Result<Self, Box<dyn std::error::Error>>;
+  fn monitor_config(controller: Arc<Mutex<Self>>) {
+    let watcher = match ConfigWatcher::new() {
+      Ok(watcher) => watcher,
+      Err(e) => {
+        eprintln!("Failed to watch config directory, hot reloading is disabled: {e}");
+        return;
+      }
+    };
+
+    while let Some(paths) = watcher.wait() {
+      let mut reloaded = false;
+
+      for path in paths {
+        match Self::reload(&path) {
+          Ok(true) => {
+            println!("Reloaded {}", path.display());
+            reloaded = true;
+          }
+          Ok(false) => {}
+          Err(e) => {
+            eprintln!("Failed to reload {}, keeping previous configuration: {e}", path.display());
+          }
+        }
+      }
+
+      if reloaded {
+        controller.lock().unwrap().reconcile().unwrap();
+      }
+    }
+  }
+
+  fn try_create(settings: SettingsHandle) -> Result<Self, Box<dyn std::error::Error>>;
+
+  /// Reloads the configuration file at `path`.
+  ///
+  /// Returns `Ok(false)` if the file is not part of the configuration.
+  fn reload(path: &Path) -> Result<bool, Box<dyn std::error::Error>>;
 }
 
 pub trait PlatformControllerOps {
@@ -65,6 +112,12 @@ pub trait PlatformControllerOps {
 
 pub trait VirtualControllerCore: Send + Sync {
   fn handle_event(&mut self, event: ControllerEvent) -> Result<(), ControllerError>;
+  fn handle_modifier(
+    &mut self,
+    modifier: StickModifier,
+    state: State,
+  ) -> Result<(), ControllerError>;
+  fn reconcile(&mut self) -> Result<(), ControllerError>;
   fn disconnect(&mut self) -> Result<(), ControllerError>;
 }
 
@@ -73,12 +126,26 @@ impl<T: VirtualController> VirtualControllerCore for T {
     VirtualController::handle_event(self, event)
   }
 
+  fn handle_modifier(
+    &mut self,
+    modifier: StickModifier,
+    state: State,
+  ) -> Result<(), ControllerError> {
+    VirtualController::handle_modifier(self, modifier, state)
+  }
+
+  fn reconcile(&mut self) -> Result<(), ControllerError> {
+    VirtualController::reconcile(self)
+  }
+
   fn disconnect(&mut self) -> Result<(), ControllerError> {
     self.disconnect()
   }
 }
 
 pub trait VirtualController: ControllerEventEmitter {
+  fn settings(&self) -> &SettingsHandle;
+
   fn left_stick(&self) -> &Mutex<JoyStickState>;
 
   fn right_stick(&self) -> &Mutex<JoyStickState>;
@@ -87,14 +154,26 @@ pub trait VirtualController: ControllerEventEmitter {
 
   fn right_stick_mut(&mut self) -> &mut Arc<Mutex<JoyStickState>>;
 
+  fn triggers(&self) -> &Mutex<TriggerState>;
+
   fn handle_event(&mut self, event: ControllerEvent) -> Result<(), ControllerError> {
     match event {
       ControllerEvent::Button(e) => self.handle_button_event(e),
       ControllerEvent::JoyStick(e) => self.handle_joystick_event(e),
+      ControllerEvent::Trigger(e) => self.emit(&[ControllerEvent::from(e)]),
     }
   }
 
+  /// Presses a button, or pulls a trigger for `l2` and `r2` and only presses them as buttons as
+  /// well if `[triggers] digital` is set.
   fn handle_button_event(&mut self, event: ButtonEvent) -> Result<(), ControllerError> {
+    if let Some(trigger) = Trigger::of(event.button()) {
+      self.triggers().lock().unwrap().press(trigger, *event.state());
+      self.handle_triggers()?;
+      if !self.settings().load().triggers().digital() {
+        return Ok(());
+      }
+    }
     self.emit(&[ControllerEvent::from(event)])
   }
 
@@ -109,17 +188,26 @@ pub trait VirtualController: ControllerEventEmitter {
     }
 
     let direction = { self.left_stick().lock().unwrap().direction() };
-    let vector = Vector::from((axis, polarity, joystick, direction));
 
     if *joystick == JoyStick::Right {
+      let vector = Vector::from((axis, polarity, joystick, direction));
       let vector = { self.right_stick().lock().unwrap().micro(vector) };
       self.move_right_stick(vector)
     } else {
-      let vector = { self.left_stick().lock().unwrap().tilt(vector) };
-      self.move_left_stick(vector, None)
+      self.handle_left_stick()
     }
   }
 
+  /// Applies a left stick modifier and moves the left stick to its new tilt right away.
+  fn handle_modifier(
+    &mut self,
+    modifier: StickModifier,
+    state: State,
+  ) -> Result<(), ControllerError> {
+    self.left_stick().lock().unwrap().set_modifier(modifier, state);
+    self.handle_left_stick()
+  }
+
   fn update_left_stick_direction(&self, axis: &Axis, polarity: &Polarity, state: &State) {
     let mut stick = self.left_stick().lock().unwrap();
 
@@ -139,20 +227,10 @@ pub trait VirtualController: ControllerEventEmitter {
     stick.update_direction();
   }
 
-  fn move_left_stick(
-    &mut self,
-    vector: Vector,
-    direction: Option<Direction>,
-  ) -> Result<(), ControllerError> {
-    let (x, y) = if let Some(direction) = direction {
-      if direction == Direction::North {
-        (0.0, -vector.dy() * 2.0)
-      } else {
-        (vector.dx(), -vector.dy())
-      }
-    } else {
-      (vector.dx(), -vector.dy())
-    };
+  fn move_left_stick(&mut self, vector: Vector) -> Result<(), ControllerError> {
+    let settings = self.settings().load();
+    let (x, y) = settings.deadzone().left().apply(vector.dx(), vector.dy());
+    let (x, y) = settings.transform().left_stick(x, -y);
 
     self.emit(&[
       Self::get_stick_event(JoyStick::Left, Axis::X, x),
@@ -161,9 +239,10 @@ pub trait VirtualController: ControllerEventEmitter {
   }
 
   fn move_right_stick(&mut self, vector: Vector) -> Result<(), ControllerError> {
+    let (x, y) = self.settings().load().deadzone().right().apply(vector.dx(), vector.dy());
     self.emit(&[
-      Self::get_stick_event(JoyStick::Right, Axis::X, vector.dx()),
-      Self::get_stick_event(JoyStick::Right, Axis::Y, vector.dy()),
+      Self::get_stick_event(JoyStick::Right, Axis::X, x),
+      Self::get_stick_event(JoyStick::Right, Axis::Y, y),
     ])
   }
 
@@ -206,12 +285,56 @@ pub trait VirtualController: ControllerEventEmitter {
     ])
   }
 
+  /// Tilts the right stick to `vector` for `duration` and recenters it afterwards.
+  ///
+  /// The tilt is emitted as is, without any of the settings, to measure how a game responds to it.
+  fn hold_right_stick(
+    &mut self,
+    vector: Vector,
+    duration: Duration,
+  ) -> Result<(), ControllerError> {
+    self.emit(&[
+      Self::get_stick_event(JoyStick::Right, Axis::X, vector.dx()),
+      Self::get_stick_event(JoyStick::Right, Axis::Y, vector.dy()),
+    ])?;
+    std::thread::sleep(duration);
+    self.center_right_stick()
+  }
+
+  /// Releases every button and recenters both sticks.
+  ///
+  /// Used after the configuration changed, since keys that are held down right now might be bound
+  /// to different buttons once they are released.
+  fn reconcile(&mut self) -> Result<(), ControllerError> {
+    let released: Vec<ControllerEvent> = ControllerButton::all()
+      .iter()
+      .filter(|button| !button.is_joystick())
+      .map(|button| ControllerEvent::from(ButtonEvent::new(*button, State::Released)))
+      .collect();
+    self.emit(&released)?;
+
+    self.center_left_stick()?;
+    self.left_stick().lock().unwrap().reset();
+    self.right_stick().lock().unwrap().reset();
+    self.triggers().lock().unwrap().reset();
+    self.emit(&[
+      ControllerEvent::from(TriggerEvent::new(Trigger::Left, 0)),
+      ControllerEvent::from(TriggerEvent::new(Trigger::Right, 0)),
+    ])?;
+    self.center_right_stick()
+  }
+
+  /// Ramps the left stick and the triggers, which both move a little every millisecond.
   fn monitor_left_stick(controller: Arc<Mutex<Self>>) -> !
   where
     Self: Sized,
   {
     loop {
-      controller.lock().unwrap().handle_left_stick().unwrap();
+      {
+        let mut controller = controller.lock().unwrap();
+        controller.handle_left_stick().unwrap();
+        controller.handle_triggers().unwrap();
+      }
       std::thread::sleep(std::time::Duration::from_millis(1));
     }
   }
@@ -221,28 +344,41 @@ pub trait VirtualController: ControllerEventEmitter {
     Self: Sized,
   {
     loop {
-      controller.lock().unwrap().handle_right_stick().unwrap();
-      std::thread::sleep(SETTINGS.tickrate());
+      let wait = {
+        let mut controller = controller.lock().unwrap();
+        controller.handle_right_stick().unwrap();
+        let tickrate = controller.settings().load().tickrate();
+        let deadline = controller.right_stick().lock().unwrap().until_deadline();
+        deadline.map_or(tickrate, |deadline| deadline.min(tickrate))
+      };
+      std::thread::sleep(wait);
+    }
+  }
+
+  fn handle_triggers(&mut self) -> Result<(), ControllerError> {
+    let events: Vec<ControllerEvent> =
+      { self.triggers().lock().unwrap().tick() }.into_iter().map(ControllerEvent::from).collect();
+    if events.is_empty() {
+      return Ok(());
     }
+    self.emit(&events)
   }
 
   fn handle_left_stick(&mut self) -> Result<(), ControllerError> {
-    let maybe_direction = { self.left_stick_mut().lock().unwrap().direction() };
-    if let Some(direction) = maybe_direction {
-      let vector = Vector::from(direction) * settings::LEFT_STICK_SENSITIVITY;
-      let vector = { self.left_stick_mut().lock().unwrap().tilt(vector) };
-      self.move_left_stick(vector, Some(direction))
-    } else {
-      self.center_left_stick()
+    let vector = { self.left_stick_mut().lock().unwrap().ramp() };
+    match vector {
+      Some(vector) => self.move_left_stick(vector),
+      None => self.center_left_stick(),
     }
   }
 
   fn handle_right_stick(&mut self) -> Result<(), ControllerError> {
     let left_stick_direction = self.left_stick().lock().unwrap().direction();
-    if self.right_stick().lock().unwrap().handle_idle(left_stick_direction) {
-      self.center_right_stick()
-    } else {
-      Ok(())
+    let vector = { self.right_stick().lock().unwrap().tick(left_stick_direction) };
+    match vector {
+      Some(vector) if vector.dx() == 0.0 && vector.dy() == 0.0 => self.center_right_stick(),
+      Some(vector) => self.move_right_stick(vector),
+      None => Ok(()),
     }
   }
 
@@ -0,0 +1,25 @@
+use crate::Trigger;
+
+/// A trigger that moved to `value`, from `0` while released to `MAX_TRIGGER_PULL`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TriggerEvent {
+  trigger: Trigger,
+  value: i32,
+}
+
+impl TriggerEvent {
+  pub fn new(trigger: Trigger, value: i32) -> Self {
+    Self {
+      trigger,
+      value,
+    }
+  }
+
+  pub fn trigger(&self) -> Trigger {
+    self.trigger
+  }
+
+  pub fn value(&self) -> i32 {
+    self.value
+  }
+}
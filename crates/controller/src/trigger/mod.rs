@@ -0,0 +1,25 @@
+mod event;
+mod state;
+
+pub use event::*;
+pub use state::*;
+
+use crate::ControllerButton;
+
+/// One of the two analog triggers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Trigger {
+  Left,
+  Right,
+}
+
+impl Trigger {
+  /// The trigger that `button` pulls, if it is one of the trigger buttons.
+  pub fn of(button: ControllerButton) -> Option<Self> {
+    match button {
+      ControllerButton::L2 => Some(Trigger::Left),
+      ControllerButton::R2 => Some(Trigger::Right),
+      _ => None,
+    }
+  }
+}
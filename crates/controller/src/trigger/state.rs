@@ -0,0 +1,171 @@
+use std::{sync::Arc, time::Instant};
+
+use crate::{Clock, State, SystemClock, Trigger, TriggerEvent};
+
+use settings::{SettingsHandle, MAX_TRIGGER_PULL};
+
+/// How far one trigger is pulled, and what was last emitted for it.
+#[derive(Debug, Clone, Copy, Default)]
+struct Pull {
+  held: bool,
+  level: f64,
+  value: i32,
+  updated: Option<Instant>,
+}
+
+impl Pull {
+  /// Moves the pull towards its target by the time that passed since the last update.
+  fn advance(&mut self, now: Instant, trigger: &settings::Trigger) {
+    let target = if self.held {
+      trigger.pull()
+    } else {
+      0.0
+    };
+    let duration = if target > self.level {
+      trigger.attack()
+    } else {
+      trigger.release()
+    };
+    let elapsed =
+      self.updated.map_or(0.0, |updated| now.saturating_duration_since(updated).as_secs_f64());
+    let step = if duration.is_zero() {
+      f64::INFINITY
+    } else {
+      elapsed / duration.as_secs_f64()
+    };
+
+    self.level = if target > self.level {
+      (self.level + step).min(target)
+    } else {
+      (self.level - step).max(target)
+    };
+    self.updated = Some(now);
+  }
+}
+
+/// The analog triggers, pulled by their keys as configured in `[triggers]`.
+#[derive(Debug)]
+pub struct TriggerState {
+  settings: SettingsHandle,
+  clock: Arc<dyn Clock>,
+  left: Pull,
+  right: Pull,
+}
+
+impl TriggerState {
+  /// Creates released triggers that use the wall clock.
+  pub fn new(settings: SettingsHandle) -> Self {
+    Self::with_clock(settings, Arc::new(SystemClock))
+  }
+
+  /// Creates released triggers that read the time from `clock`.
+  pub fn with_clock(settings: SettingsHandle, clock: Arc<dyn Clock>) -> Self {
+    Self {
+      settings,
+      clock,
+      left: Default::default(),
+      right: Default::default(),
+    }
+  }
+
+  /// Holds or releases the key of `trigger`.
+  pub fn press(&mut self, trigger: Trigger, state: State) {
+    let settings = self.settings.load();
+    let now = self.clock.now();
+    let (pull, config) = match trigger {
+      Trigger::Left => (&mut self.left, settings.triggers().left()),
+      Trigger::Right => (&mut self.right, settings.triggers().right()),
+    };
+    pull.advance(now, config);
+    pull.held = state != State::Released;
+  }
+
+  /// Moves both triggers towards their pull and returns an event for each whose value changed.
+  pub fn tick(&mut self) -> Vec<TriggerEvent> {
+    let settings = self.settings.load();
+    let now = self.clock.now();
+    let triggers = settings.triggers();
+
+    [
+      (Trigger::Left, &mut self.left, triggers.left()),
+      (Trigger::Right, &mut self.right, triggers.right()),
+    ]
+    .into_iter()
+    .filter_map(|(trigger, pull, config)| {
+      pull.advance(now, config);
+      let value = (pull.level * MAX_TRIGGER_PULL).round() as i32;
+      if value == pull.value {
+        return None;
+      }
+      pull.value = value;
+      Some(TriggerEvent::new(trigger, value))
+    })
+    .collect()
+  }
+
+  /// Releases both triggers at once.
+  pub fn reset(&mut self) {
+    self.left = Default::default();
+    self.right = Default::default();
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use crate::ManualClock;
+
+  use settings::ControllerSettings;
+
+  use std::time::Duration;
+
+  fn triggers(toml: &str) -> (TriggerState, Arc<ManualClock>) {
+    let settings = SettingsHandle::new(ControllerSettings::from_toml("test", toml).unwrap());
+    let clock = Arc::new(ManualClock::default());
+    (TriggerState::with_clock(settings, clock.clone()), clock)
+  }
+
+  fn values(events: Vec<TriggerEvent>) -> Vec<(Trigger, i32)> {
+    events.into_iter().map(|event| (event.trigger(), event.value())).collect()
+  }
+
+  #[test]
+  fn test_triggers_are_pulled_fully_at_once_by_default() {
+    let (mut triggers, _) = triggers("");
+    triggers.press(Trigger::Right, State::Pressed);
+
+    assert_eq!(values(triggers.tick()), [(Trigger::Right, 255)]);
+    assert!(triggers.tick().is_empty());
+
+    triggers.press(Trigger::Right, State::Released);
+
+    assert_eq!(values(triggers.tick()), [(Trigger::Right, 0)]);
+  }
+
+  #[test]
+  fn test_partial_pull_ramps_up_and_back() {
+    let (mut triggers, clock) =
+      triggers("[triggers.left]\npull = 0.5\nattack = 100\nrelease = 200");
+    triggers.press(Trigger::Left, State::Pressed);
+
+    assert!(triggers.tick().is_empty());
+
+    clock.advance(Duration::from_millis(20));
+
+    assert_eq!(values(triggers.tick()), [(Trigger::Left, 51)]);
+
+    clock.advance(Duration::from_millis(100));
+
+    assert_eq!(values(triggers.tick()), [(Trigger::Left, 128)]);
+
+    triggers.press(Trigger::Left, State::Released);
+    clock.advance(Duration::from_millis(50));
+
+    assert_eq!(values(triggers.tick()), [(Trigger::Left, 64)]);
+
+    clock.advance(Duration::from_millis(50));
+
+    assert_eq!(values(triggers.tick()), [(Trigger::Left, 0)]);
+  }
+}
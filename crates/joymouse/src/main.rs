@@ -1,12 +1,286 @@
-use controller::PlatformControllerManager;
+use clap::{Args, Parser, Subcommand};
+use controller::{PlatformControllerManager, Vector, VirtualController};
+use platform::simulator::{Format, SimulatorError, Trace};
+use serde::Serialize;
+use settings::{Calibration, Layer, MAX_STICK_TILT};
+
+use std::{
+  io::Write,
+  path::{Path, PathBuf},
+  time::Duration,
+};
+
+#[derive(Debug, Parser)]
+#[command(version, about = "Turn your mouse into a virtual gamepad")]
+struct Cli {
+  /// Profile from the `profiles` config directory to start with
+  #[arg(long, env = "JOYMOUSE_PROFILE")]
+  profile: Option<String>,
+
+  /// Settings file to read instead of `joymouse.toml` in the config directory
+  #[arg(long, env = "JOYMOUSE_CONFIG")]
+  config: Option<PathBuf>,
+
+  #[command(flatten)]
+  settings: SettingsArgs,
+
+  #[command(subcommand)]
+  command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+  /// Upgrade configuration files written for an older version, keeping a `.bak` copy of each
+  Migrate,
+
+  /// Run recorded mouse movement and key events through the current configuration and print
+  /// everything the controller would emit
+  Simulate {
+    /// `.csv` or `.json` file with the timestamped input
+    trace: PathBuf,
+
+    /// File to write the output timeline to instead of printing it
+    #[arg(long, short)]
+    output: Option<PathBuf>,
+
+    /// Format of the output timeline, guessed from the output file by default
+    #[arg(long, value_name = "csv|json")]
+    format: Option<Format>,
+  },
+
+  /// Measure how fast the game turns the camera at different tilts and store a calibration that
+  /// makes the turn rate proportional to the tilt in the active profile
+  Calibrate {
+    /// Number of tilts to measure, evenly spaced up to a full tilt
+    #[arg(long, default_value_t = 10)]
+    steps: u32,
+
+    /// How long each tilt is held in milliseconds
+    #[arg(long, value_name = "MS", default_value_t = 1000)]
+    duration: u64,
+  },
+}
+
+/// Overrides for single fields of `joymouse.toml`, which win over every other source.
+#[derive(Debug, Args, Serialize)]
+#[command(next_help_heading = "Settings")]
+struct SettingsArgs {
+  /// Update interval in milliseconds
+  #[arg(long, value_name = "MS")]
+  #[serde(skip_serializing_if = "Option::is_none")]
+  tickrate: Option<u64>,
+
+  /// Time in milliseconds before the stick auto-centers when idle
+  #[arg(long, value_name = "MS")]
+  #[serde(skip_serializing_if = "Option::is_none")]
+  mouse_idle_timeout: Option<u64>,
+
+  /// Maximum analog stick tilt value
+  #[arg(long)]
+  #[serde(skip_serializing_if = "Option::is_none")]
+  max_tilt_range: Option<f64>,
+
+  /// Minimum analog stick tilt value
+  #[arg(long)]
+  #[serde(skip_serializing_if = "Option::is_none")]
+  min_tilt_range: Option<f64>,
+
+  /// Mouse-to-stick sensitivity multiplier
+  #[arg(long)]
+  #[serde(skip_serializing_if = "Option::is_none")]
+  sensitivity: Option<f64>,
+
+  /// Multiplier for diagonal movement
+  #[arg(long)]
+  #[serde(skip_serializing_if = "Option::is_none")]
+  diagonal_boost: Option<f64>,
+
+  /// Minimum clamped speed
+  #[arg(long)]
+  #[serde(skip_serializing_if = "Option::is_none")]
+  min_speed_clamp: Option<f64>,
+
+  /// Maximum clamped speed
+  #[arg(long)]
+  #[serde(skip_serializing_if = "Option::is_none")]
+  max_speed_clamp: Option<f64>,
+
+  /// Threshold to switch micro → macro motion
+  #[arg(long)]
+  #[serde(skip_serializing_if = "Option::is_none")]
+  motion_threshold_micro_macro: Option<f64>,
+
+  /// Threshold for fast flick detection
+  #[arg(long)]
+  #[serde(skip_serializing_if = "Option::is_none")]
+  motion_threshold_macro_flick: Option<f64>,
+
+  /// Threshold to switch macro → micro motion
+  #[arg(long)]
+  #[serde(skip_serializing_if = "Option::is_none")]
+  motion_threshold_macro_micro: Option<f64>,
+
+  /// Threshold to recover from macro to micro
+  #[arg(long)]
+  #[serde(skip_serializing_if = "Option::is_none")]
+  motion_threshold_micro_macro_recover: Option<f64>,
+
+  /// Algorithm that turns mouse movement into right stick positions
+  #[arg(long, value_name = "NAME")]
+  #[serde(skip_serializing_if = "Option::is_none")]
+  mapper: Option<String>,
+
+  /// Carry movement the mapper could not emit yet forward to later ticks
+  #[arg(long, value_name = "BOOL")]
+  #[serde(skip_serializing_if = "Option::is_none")]
+  preserve_distance: Option<bool>,
+
+  /// How the motion mapper reacts to a flick of the mouse
+  #[arg(long, value_name = "MODE")]
+  #[serde(skip_serializing_if = "Option::is_none")]
+  flick: Option<String>,
+}
+
+impl SettingsArgs {
+  fn layer(&self) -> Layer {
+    let table = toml::Table::try_from(self).expect("failed to serialize command line settings");
+    Layer::new("command line", table)
+  }
+}
+
+fn migrate() {
+  match profile::migrate() {
+    Ok(migrated) if migrated.is_empty() => println!("All configuration files are up to date"),
+    Ok(migrated) => {
+      for path in migrated {
+        println!("Upgraded {}", path.display());
+      }
+    }
+    Err(e) => {
+      eprintln!("Failed to migrate configuration:\n{e}");
+      std::process::exit(1);
+    }
+  }
+}
+
+fn simulate(
+  trace: &Path,
+  output: Option<&Path>,
+  format: Option<Format>,
+) -> Result<(), SimulatorError> {
+  let trace = Trace::read(trace)?;
+  let bindings = bindings::BINDINGS.load().as_ref().clone();
+  let timeline = platform::simulator::simulate(&trace, settings::SETTINGS.clone(), bindings)?;
+  let format = format.or_else(|| output.and_then(Format::from_path)).unwrap_or(Format::Csv);
+
+  match output {
+    Some(path) => timeline.write(path, format),
+    None => {
+      print!("{}", timeline.format(format));
+      Ok(())
+    }
+  }
+}
+
+fn prompt(message: &str) -> std::io::Result<String> {
+  print!("{message}");
+  std::io::stdout().flush()?;
+  let mut line = String::new();
+  std::io::stdin().read_line(&mut line)?;
+  Ok(line)
+}
+
+fn calibrate(
+  controller: &mut impl VirtualController,
+  steps: u32,
+  duration: Duration,
+) -> Result<PathBuf, Box<dyn std::error::Error>> {
+  println!("Aim at a landmark in the game without moving the mouse.");
+  println!("For each step, the right stick is held to the right for {} ms.", duration.as_millis());
+  println!("Afterwards, enter how many degrees the camera turned, e.g. 360 for a full turn.");
+
+  let mut measurements = Vec::new();
+
+  for step in 1..=steps {
+    let tilt = f64::from(step) / f64::from(steps);
+    prompt(&format!("\nStep {step}/{steps} at {:.0}% tilt, press Enter to start", tilt * 100.0))?;
+    controller.hold_right_stick(Vector::new(tilt * MAX_STICK_TILT, 0.0), duration)?;
+
+    let degrees = loop {
+      match prompt("Degrees turned: ")?.trim().parse::<f64>() {
+        Ok(degrees) => break degrees,
+        Err(_) => println!("Please enter a number"),
+      }
+    };
+    measurements.push((tilt, degrees));
+  }
+
+  let calibration = Calibration::fit(&measurements)?;
+  Ok(profile::store_calibration(&calibration)?)
+}
 
 fn main() {
+  let cli = Cli::parse();
+
+  if let Some(config) = cli.config {
+    settings::set_config_path(std::path::absolute(&config).unwrap_or(config));
+  }
+
+  settings::set_overrides(vec![settings::environment(std::env::vars()), cli.settings.layer()]);
+
+  if let Some(Command::Migrate) = cli.command {
+    migrate();
+    return;
+  }
+
+  if let Err(e) = profile::activate(cli.profile.as_deref()) {
+    eprintln!("Failed to load configuration:\n{e}");
+    std::process::exit(1);
+  }
+
+  match cli.command {
+    Some(Command::Simulate {
+      trace,
+      output,
+      format,
+    }) => {
+      if let Err(e) = simulate(&trace, output.as_deref(), format) {
+        eprintln!("Failed to simulate {}: {e}", trace.display());
+        std::process::exit(1);
+      }
+      return;
+    }
+    Some(Command::Calibrate {
+      steps,
+      duration,
+    }) => {
+      #[cfg(not(windows))]
+      let controller = platform::linux::Controller::try_create(settings::SETTINGS.clone());
+      #[cfg(windows)]
+      let controller = platform::windows::Controller::try_create(settings::SETTINGS.clone());
+
+      let result = controller.and_then(|mut controller| {
+        calibrate(&mut controller, steps, Duration::from_millis(duration))
+      });
+
+      match result {
+        Ok(path) => println!("Stored the calibration in {}", path.display()),
+        Err(e) => {
+          eprintln!("Failed to calibrate: {e}");
+          std::process::exit(1);
+        }
+      }
+      return;
+    }
+    _ => {}
+  }
+
   #[cfg(not(windows))]
   {
-    platform::linux::Controller::run().unwrap()
+    platform::linux::Controller::run(settings::SETTINGS.clone()).unwrap()
   }
   #[cfg(windows)]
   {
-    platform::windows::Controller::run().unwrap()
+    platform::windows::Controller::run(settings::SETTINGS.clone()).unwrap()
   }
 }
@@ -2,3 +2,84 @@
 pub mod linux;
 #[cfg(windows)]
 pub mod windows;
+
+pub mod simulator;
+
+use controller::{State, VirtualControllerCore};
+use io::Key;
+use profile::{ChordTracker, CHORDS};
+
+use std::{
+  path::Path,
+  sync::{LazyLock, Mutex},
+};
+
+static CHORD_TRACKER: LazyLock<Mutex<ChordTracker>> = LazyLock::new(Default::default);
+
+fn reload(path: &Path) -> Result<bool, Box<dyn std::error::Error>> {
+  let is = |other: Option<std::path::PathBuf>| other.is_some_and(|other| same_file(path, &other));
+
+  let is_profile = profile::profiles_dir().is_some_and(|dir| path.starts_with(dir));
+
+  if !is(settings::config_path()) && !is(bindings::bindings_path()) && !is_profile {
+    return Ok(false);
+  }
+
+  profile::reload()?;
+  Ok(true)
+}
+
+/// Whether `path` and `other` name the same file, even if only one of them is relative.
+fn same_file(path: &Path, other: &Path) -> bool {
+  let absolute = |path: &Path| std::path::absolute(path).unwrap_or_else(|_| path.to_owned());
+  absolute(path) == absolute(other)
+}
+
+/// Switches the profile if `key` completed one of the profile chords.
+///
+/// Returns `true` if the key event was consumed by a profile chord. Once a chord is complete, the
+/// buttons its other keys pressed are released and their events are consumed until they are
+/// released, so that they do not press those buttons again.
+fn switch_profile(key: Key, state: State, controller: &mut dyn VirtualControllerCore) -> bool {
+  let chords = CHORDS.load();
+  let (consumed, completed) = {
+    let mut tracker = CHORD_TRACKER.lock().unwrap();
+    (tracker.is_consumed(key), tracker.update(&chords, key, state))
+  };
+  let Some(name) = completed else {
+    return consumed;
+  };
+
+  match profile::activate(Some(&name)) {
+    Ok(()) => println!("Switched to profile {name}"),
+    Err(e) => eprintln!("Failed to switch to profile {name}: {e}"),
+  }
+  controller.reconcile().unwrap();
+
+  true
+}
+
+/// Applies the left stick modifier that `key` is bound to, if any.
+///
+/// Returns `true` if the key event was consumed by a modifier.
+fn modify_left_stick(key: Key, state: State, controller: &mut dyn VirtualControllerCore) -> bool {
+  let Some(modifier) = bindings::BINDINGS.load().modifier(&key) else {
+    return false;
+  };
+
+  controller.handle_modifier(modifier, state).unwrap();
+  true
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_same_file_compares_whole_paths() {
+    let cwd = std::env::current_dir().unwrap();
+
+    assert!(same_file(&cwd.join("joymouse.toml"), Path::new("joymouse.toml")));
+    assert!(!same_file(&cwd.join("other").join("joymouse.toml"), Path::new("joymouse.toml")));
+  }
+}
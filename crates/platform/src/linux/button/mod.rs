@@ -2,7 +2,7 @@ mod event;
 
 pub use event::*;
 
-use bindings::KEYBOARD_BUTTON_MAP;
+use bindings::BINDINGS;
 use controller::{ButtonError, ControllerButton};
 use io::Key;
 
@@ -37,8 +37,8 @@ pub fn try_from_keycode_for_controller_button(
   code: KeyCode,
 ) -> Result<ControllerButton, ButtonError> {
   let key = Key::try_from(code).map_err(|_| ButtonError::UnsupportedKeyCode(code.code()))?;
-  if let Some(button) = KEYBOARD_BUTTON_MAP.get(&key) {
-    Ok(*button)
+  if let Some(button) = BINDINGS.load().button(&key) {
+    Ok(button)
   } else {
     Err(ButtonError::UnsupportedKeyCode(code.code()))
   }
@@ -1,8 +1,8 @@
-use bindings::JOYSTICK_KEYS;
-use controller::{ControllerError, ControllerEvent};
+use bindings::BINDINGS;
+use controller::{ControllerError, ControllerEvent, Trigger};
 use io::Key;
 
-use evdev::{EventSummary, InputEvent, KeyEvent, RelativeAxisEvent};
+use evdev::{AbsoluteAxisCode, EventSummary, EventType, InputEvent, KeyEvent, RelativeAxisEvent};
 
 use crate::linux::{
   button::{from_button_event_for_input_event, try_from_key_event_for_button_event},
@@ -18,6 +18,13 @@ pub fn from_controller_event_for_input_event(event: ControllerEvent) -> InputEve
     ControllerEvent::JoyStick(joystick_event) => {
       from_joystick_event_for_input_event(joystick_event)
     }
+    ControllerEvent::Trigger(trigger_event) => {
+      let code = match trigger_event.trigger() {
+        Trigger::Left => AbsoluteAxisCode::ABS_Z,
+        Trigger::Right => AbsoluteAxisCode::ABS_RZ,
+      };
+      InputEvent::new(EventType::ABSOLUTE.0, code.0, trigger_event.value())
+    }
   }
 }
 
@@ -27,7 +34,7 @@ pub fn try_from_key_event_for_controller_event(
   let code = event.code();
   let key = Key::try_from(code).map_err(|_| ControllerError::UnsupportedEvent)?;
 
-  if JOYSTICK_KEYS.key_is_joystick_key(key) {
+  if BINDINGS.load().joystick_keys().key_is_joystick_key(key) {
     let joystick_event = try_from_key_event_for_joystick_event(event)?;
     let event = ControllerEvent::from(joystick_event);
     return Ok(event);
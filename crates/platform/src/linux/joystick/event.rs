@@ -1,4 +1,4 @@
-use bindings::JOYSTICK_KEYS;
+use bindings::BINDINGS;
 use controller::{Axis, JoyStick, JoyStickError, JoyStickEvent, Polarity, State};
 use evdev::{AbsoluteAxisCode, EventType, InputEvent, KeyEvent, RelativeAxisEvent};
 
@@ -31,7 +31,7 @@ pub fn try_from_key_event_for_joystick_event(
 ) -> Result<JoyStickEvent, JoyStickError> {
   let code = event.code();
   let joystick = JoyStick::Left;
-  let axis = try_from_jk_kc_for_axis(&JOYSTICK_KEYS, code)?;
+  let axis = try_from_jk_kc_for_axis(BINDINGS.load().joystick_keys(), code)?;
   let button = try_from_keycode_for_controller_button(code)?;
   let state = State::try_from(event.value())?;
   let polarity = try_from_event_tuple_for_polarity(&axis, &button, code)?;
@@ -8,27 +8,32 @@ use crate::linux::event::{
 
 use controller::{
   ControllerError, ControllerEvent, ControllerEventEmitter, JoyStickState,
-  PlatformControllerManager, PlatformControllerOps, VirtualController, VirtualControllerCore,
+  PlatformControllerManager, PlatformControllerOps, State, TriggerState, VirtualController,
+  VirtualControllerCore,
 };
-use settings::{MAX_STICK_TILT, MIN_STICK_TILT};
+use io::Key;
+use settings::{SettingsHandle, MAX_STICK_TILT, MAX_TRIGGER_PULL, MIN_STICK_TILT};
 
 use std::{
   collections::HashMap,
   os::fd::{AsRawFd, RawFd},
+  path::Path,
   sync::{Arc, Mutex},
 };
 
 use epoll::{Event, Events};
 use evdev::{
-  uinput::VirtualDevice, AbsInfo, AbsoluteAxisCode, AttributeSet, BusType, Device, EventType,
-  InputEvent, InputId, KeyCode, MiscCode, RelativeAxisCode, UinputAbsSetup,
+  uinput::VirtualDevice, AbsInfo, AbsoluteAxisCode, AttributeSet, BusType, Device, EventSummary,
+  EventType, InputEvent, InputId, KeyCode, MiscCode, RelativeAxisCode, UinputAbsSetup,
 };
 
 #[derive(Debug)]
 pub struct Controller {
   virtual_device: VirtualDevice,
+  settings: SettingsHandle,
   left_stick: Arc<Mutex<JoyStickState>>,
   right_stick: Arc<Mutex<JoyStickState>>,
+  triggers: Mutex<TriggerState>,
 }
 
 #[rustfmt::skip]
@@ -48,6 +53,10 @@ impl ControllerEventEmitter for Controller {
 }
 
 impl VirtualController for Controller {
+  fn settings(&self) -> &SettingsHandle {
+    &self.settings
+  }
+
   fn left_stick(&self) -> &Mutex<JoyStickState> {
     &self.left_stick
   }
@@ -63,18 +72,28 @@ impl VirtualController for Controller {
   fn right_stick_mut(&mut self) -> &mut Arc<Mutex<JoyStickState>> {
     &mut self.right_stick
   }
+
+  fn triggers(&self) -> &Mutex<TriggerState> {
+    &self.triggers
+  }
 }
 
 impl PlatformControllerManager for Controller {
   type Ops = LinuxOps;
 
-  fn try_create() -> Result<Self, Box<dyn std::error::Error>> {
+  fn try_create(settings: SettingsHandle) -> Result<Self, Box<dyn std::error::Error>> {
     Ok(Self {
       virtual_device: LinuxOps::create_virtual_controller()?,
-      left_stick: Arc::new(Mutex::new(JoyStickState::default())),
-      right_stick: Arc::new(Mutex::new(JoyStickState::default())),
+      left_stick: Arc::new(Mutex::new(JoyStickState::new(settings.clone()))),
+      right_stick: Arc::new(Mutex::new(JoyStickState::new(settings.clone()))),
+      triggers: Mutex::new(TriggerState::new(settings.clone())),
+      settings,
     })
   }
+
+  fn reload(path: &Path) -> Result<bool, Box<dyn std::error::Error>> {
+    crate::reload(path)
+  }
 }
 
 pub struct LinuxOps;
@@ -129,6 +148,10 @@ impl PlatformControllerOps for LinuxOps {
     let rx_axis = UinputAbsSetup::new(AbsoluteAxisCode::ABS_RX, axis_info);
     let ry_axis = UinputAbsSetup::new(AbsoluteAxisCode::ABS_RY, axis_info);
 
+    let trigger_info = AbsInfo::new(0, 0, MAX_TRIGGER_PULL as i32, noise_tolerance, deadzone, 0);
+    let z_axis = UinputAbsSetup::new(AbsoluteAxisCode::ABS_Z, trigger_info);
+    let rz_axis = UinputAbsSetup::new(AbsoluteAxisCode::ABS_RZ, trigger_info);
+
     let virtual_device = builder
       .name(name)
       .input_id(input_id)
@@ -137,6 +160,8 @@ impl PlatformControllerOps for LinuxOps {
       .with_absolute_axis(&y_axis)?
       .with_absolute_axis(&rx_axis)?
       .with_absolute_axis(&ry_axis)?
+      .with_absolute_axis(&z_axis)?
+      .with_absolute_axis(&rz_axis)?
       .build()?;
 
     Ok(virtual_device)
@@ -185,7 +210,20 @@ impl PlatformControllerOps for LinuxOps {
 
         if let Some(device) = fd_map.get_mut(&fd) {
           for event in device.fetch_events().unwrap() {
-            if let Ok(event) = try_from_event_summary_for_controller_event(event.destructure()) {
+            let summary = event.destructure();
+
+            if let EventSummary::Key(event, code, _) = summary {
+              if let (Ok(key), Ok(state)) = (Key::try_from(code), State::try_from(event.value())) {
+                let mut controller = controller.lock().unwrap();
+                if crate::switch_profile(key, state, &mut *controller)
+                  || crate::modify_left_stick(key, state, &mut *controller)
+                {
+                  continue;
+                }
+              }
+            }
+
+            if let Ok(event) = try_from_event_summary_for_controller_event(summary) {
               controller.lock().unwrap().handle_event(event).unwrap();
             }
           }
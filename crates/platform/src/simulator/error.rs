@@ -0,0 +1,38 @@
+use std::path::PathBuf;
+
+use controller::ControllerError;
+
+#[derive(Debug)]
+pub enum SimulatorError {
+  Io(PathBuf, std::io::Error),
+  Format(PathBuf),
+  Parse(usize, String),
+  Controller(ControllerError),
+}
+
+impl From<ControllerError> for SimulatorError {
+  fn from(v: ControllerError) -> Self {
+    Self::Controller(v)
+  }
+}
+
+impl std::fmt::Display for SimulatorError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      SimulatorError::Io(path, e) => {
+        write!(f, "failed to access {}: {}", path.display(), e)
+      }
+      SimulatorError::Format(path) => {
+        write!(f, "unknown format of {}, expected a .csv or .json file", path.display())
+      }
+      SimulatorError::Parse(line, reason) => {
+        write!(f, "invalid trace event on line {}: {}", line, reason)
+      }
+      SimulatorError::Controller(e) => {
+        write!(f, "{}", e)
+      }
+    }
+  }
+}
+
+impl std::error::Error for SimulatorError {}
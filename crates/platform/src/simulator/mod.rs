@@ -0,0 +1,348 @@
+//! Runs recorded input through the same pipeline as a real controller, without any devices.
+
+mod error;
+mod timeline;
+mod trace;
+
+pub use error::*;
+pub use timeline::*;
+pub use trace::*;
+
+use bindings::KeyMap;
+use controller::{
+  Axis, ButtonEvent, Clock, ControllerError, ControllerEvent, ControllerEventEmitter, JoyStick,
+  JoyStickEvent, JoyStickState, ManualClock, Polarity, State, Trigger, TriggerState,
+  VirtualController,
+};
+use settings::SettingsHandle;
+
+use std::{
+  collections::HashMap,
+  path::Path,
+  sync::{Arc, Mutex},
+  time::{Duration, Instant},
+};
+
+/// The file format of traces and timelines.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+  Csv,
+  Json,
+}
+
+impl Format {
+  /// Guesses the format from the extension of `path`.
+  pub fn from_path(path: &Path) -> Option<Self> {
+    path.extension().and_then(|extension| extension.to_str()?.parse().ok())
+  }
+}
+
+impl std::str::FromStr for Format {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s.to_ascii_lowercase().as_str() {
+      "csv" => Ok(Self::Csv),
+      "json" => Ok(Self::Json),
+      other => Err(format!("unknown format '{other}', expected csv or json")),
+    }
+  }
+}
+
+/// Feeds `trace` into a virtual controller with the given settings and bindings and records
+/// every change of its outputs.
+///
+/// The sticks are updated as often as while running for real, and the simulation continues after
+/// the last event until the right stick had the chance to recenter.
+pub fn simulate(
+  trace: &Trace,
+  settings: SettingsHandle,
+  bindings: KeyMap,
+) -> Result<Timeline, SimulatorError> {
+  let mut simulator = Simulator::new(settings, bindings);
+  simulator.run(trace)?;
+  Ok(simulator.timeline)
+}
+
+/// A virtual controller that records its output instead of sending it to a device.
+#[derive(Debug)]
+struct Simulator {
+  settings: SettingsHandle,
+  bindings: KeyMap,
+  clock: Arc<ManualClock>,
+  start: Instant,
+  left_stick: Arc<Mutex<JoyStickState>>,
+  right_stick: Arc<Mutex<JoyStickState>>,
+  triggers: Mutex<TriggerState>,
+  outputs: HashMap<String, i32>,
+  timeline: Timeline,
+}
+
+impl Simulator {
+  fn new(settings: SettingsHandle, bindings: KeyMap) -> Self {
+    let clock = Arc::new(ManualClock::default());
+    let stick = || {
+      let stick = JoyStickState::with_clock(settings.clone(), clock.clone());
+      Arc::new(Mutex::new(stick))
+    };
+    Self {
+      left_stick: stick(),
+      right_stick: stick(),
+      triggers: Mutex::new(TriggerState::with_clock(settings.clone(), clock.clone())),
+      start: clock.now(),
+      clock,
+      settings,
+      bindings,
+      outputs: Default::default(),
+      timeline: Default::default(),
+    }
+  }
+
+  fn run(&mut self, trace: &Trace) -> Result<(), ControllerError> {
+    let (tickrate, idle_timeout, recenter, release) = {
+      let settings = self.settings.load();
+      let timeouts = settings.recenter().idle_timeout();
+      let idle_timeout = timeouts.longest().unwrap_or_default().max(settings.mouse_idle_timeout());
+      let triggers = settings.triggers();
+      let release = triggers.left().release().max(triggers.right().release());
+      let tickrate = settings.tickrate().as_millis().max(1) as u64;
+      (tickrate, idle_timeout, settings.recenter().duration(), release)
+    };
+    let last = trace.events().last().map_or(0, |event| event.time());
+    let tail = recenter.max(release).as_millis() as u64;
+    let end = last + 2 * (idle_timeout.as_millis() as u64 + tickrate) + tail;
+    let mut events = trace.events().iter().peekable();
+
+    // Both sticks and the triggers are driven like their monitor threads do, the left stick and
+    // the triggers every millisecond and the right stick once per tick.
+    for time in 0..=end {
+      self.clock.set(self.start + Duration::from_millis(time));
+
+      while let Some(event) = events.next_if(|event| event.time() <= time) {
+        self.input(event.input())?;
+      }
+
+      self.handle_left_stick()?;
+      self.handle_triggers()?;
+      if time % tickrate == 0 {
+        self.handle_right_stick()?;
+      }
+    }
+
+    Ok(())
+  }
+
+  fn input(&mut self, input: Input) -> Result<(), ControllerError> {
+    match input {
+      Input::Mouse {
+        dx,
+        dy,
+      } => {
+        if dx != 0 {
+          self.handle_event(stick_event(JoyStick::Right, Axis::X, polarity(dx), State::Pressed))?;
+        }
+        if dy != 0 {
+          self.handle_event(stick_event(
+            JoyStick::Right,
+            Axis::Y,
+            polarity(-dy),
+            State::Pressed,
+          ))?;
+        }
+        Ok(())
+      }
+      Input::Key {
+        key,
+        state,
+      } => {
+        if let Some(modifier) = self.bindings.modifier(&key) {
+          return self.handle_modifier(modifier, state);
+        }
+
+        let keys = self.bindings.joystick_keys();
+        let event = match key {
+          k if keys.key_is_forward(k) => stick_event(JoyStick::Left, Axis::Y, polarity(1), state),
+          k if keys.key_is_backward(k) => stick_event(JoyStick::Left, Axis::Y, polarity(-1), state),
+          k if keys.key_is_port(k) => stick_event(JoyStick::Left, Axis::X, polarity(-1), state),
+          k if keys.key_is_starboard(k) => stick_event(JoyStick::Left, Axis::X, polarity(1), state),
+          k => match self.bindings.button(&k) {
+            Some(button) => ControllerEvent::from(ButtonEvent::new(button, state)),
+            None => return Ok(()),
+          },
+        };
+        self.handle_event(event)
+      }
+    }
+  }
+
+  fn record(&mut self, event: &ControllerEvent) {
+    let (output, value) = match event {
+      ControllerEvent::Button(event) => {
+        (event.button().to_string(), i32::from(*event.state() != State::Released))
+      }
+      ControllerEvent::JoyStick(event) => {
+        let value = f64::from(event.polarity()) as i32;
+        match (event.joystick(), event.axis()) {
+          (JoyStick::Left, Axis::X) => ("left_x".to_string(), value),
+          (JoyStick::Left, Axis::Y) => ("left_y".to_string(), -value),
+          (JoyStick::Right, Axis::X) => ("right_x".to_string(), value),
+          (JoyStick::Right, Axis::Y) => ("right_y".to_string(), value),
+        }
+      }
+      ControllerEvent::Trigger(event) => match event.trigger() {
+        Trigger::Left => ("left_trigger".to_string(), event.value()),
+        Trigger::Right => ("right_trigger".to_string(), event.value()),
+      },
+    };
+
+    if self.outputs.get(&output).copied().unwrap_or_default() != value {
+      let time = self.clock.now().duration_since(self.start).as_millis() as u64;
+      self.timeline.push(Sample::new(time, output.as_str(), value));
+      self.outputs.insert(output, value);
+    }
+  }
+}
+
+/// Builds a polarity like the Windows backend does, with a positive strength for both signs.
+fn polarity(value: i32) -> Polarity {
+  match value.signum() {
+    1 => Polarity::Positive(value),
+    -1 => Polarity::Negative(value.abs()),
+    _ => Polarity::Neutral,
+  }
+}
+
+fn stick_event(
+  joystick: JoyStick,
+  axis: Axis,
+  polarity: Polarity,
+  state: State,
+) -> ControllerEvent {
+  ControllerEvent::from(JoyStickEvent::new(joystick, axis, polarity, state))
+}
+
+impl ControllerEventEmitter for Simulator {
+  fn emit(&mut self, events: &[ControllerEvent]) -> Result<(), ControllerError> {
+    for event in events {
+      self.record(event);
+    }
+    Ok(())
+  }
+
+  fn disconnect(&mut self) -> Result<(), ControllerError> {
+    Ok(())
+  }
+}
+
+impl VirtualController for Simulator {
+  fn settings(&self) -> &SettingsHandle {
+    &self.settings
+  }
+
+  fn left_stick(&self) -> &Mutex<JoyStickState> {
+    &self.left_stick
+  }
+
+  fn right_stick(&self) -> &Mutex<JoyStickState> {
+    &self.right_stick
+  }
+
+  fn left_stick_mut(&mut self) -> &mut Arc<Mutex<JoyStickState>> {
+    &mut self.left_stick
+  }
+
+  fn right_stick_mut(&mut self) -> &mut Arc<Mutex<JoyStickState>> {
+    &mut self.right_stick
+  }
+
+  fn triggers(&self) -> &Mutex<TriggerState> {
+    &self.triggers
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use settings::{ControllerSettings, MAX_STICK_TILT};
+
+  fn run(csv: &str) -> Timeline {
+    run_with(csv, "")
+  }
+
+  fn run_with(csv: &str, toml: &str) -> Timeline {
+    let trace = Trace::parse(csv, Format::Csv).unwrap();
+    let settings = SettingsHandle::new(ControllerSettings::from_toml("test", toml).unwrap());
+    simulate(&trace, settings, KeyMap::default()).unwrap()
+  }
+
+  fn values<'a>(timeline: &'a Timeline, output: &'a str) -> Vec<(u64, i32)> {
+    timeline.output(output).map(|sample| (sample.time(), sample.value())).collect()
+  }
+
+  #[test]
+  fn test_flick_up_tilts_the_right_stick_up_and_recenters() {
+    let timeline = run("0,mouse,0,-40\n1,mouse,0,-40");
+    let right_y = values(&timeline, "right_y");
+
+    assert_eq!(right_y.first().unwrap().0, 1);
+    assert!(right_y.first().unwrap().1 > 0);
+    assert_eq!(right_y.last().unwrap().1, 0);
+    assert!(values(&timeline, "right_x").is_empty());
+  }
+
+  #[test]
+  fn test_keys_move_the_left_stick_and_press_buttons() {
+    let timeline =
+      run("0,key,w,pressed\n5,key,space,pressed\n20,key,space,released\n50,key,w,released");
+
+    assert_eq!(values(&timeline, "left_y"), [(0, MAX_STICK_TILT as i32), (50, 0)]);
+    assert_eq!(values(&timeline, "south"), [(5, 1), (20, 0)]);
+  }
+
+  #[test]
+  fn test_modifier_keys_slow_down_the_left_stick() {
+    let timeline = run(
+      "0,key,w,pressed\n10,key,left_shift,pressed\n20,key,f1,pressed\n30,key,left_shift,released",
+    );
+    let full = MAX_STICK_TILT as i32;
+
+    assert_eq!(
+      values(&timeline, "left_y"),
+      [
+        (0, full),
+        (10, full / 2),
+        (20, (MAX_STICK_TILT * 0.15) as i32),
+        (30, (MAX_STICK_TILT * 0.3) as i32)
+      ]
+    );
+  }
+
+  #[test]
+  fn test_trigger_keys_pull_the_trigger_and_press_the_button() {
+    let timeline = run("0,key,q,pressed\n40,key,q,released");
+
+    assert_eq!(values(&timeline, "left_trigger"), [(0, 255), (40, 0)]);
+    assert_eq!(values(&timeline, "l2"), [(0, 1), (40, 0)]);
+  }
+
+  #[test]
+  fn test_analog_only_trigger_ramps_back_on_release() {
+    let timeline = run_with(
+      "0,key,x,pressed\n40,key,x,released",
+      "[triggers]\ndigital = false\n[triggers.right]\npull = 0.4\nrelease = 100",
+    );
+    let right_trigger = values(&timeline, "right_trigger");
+
+    assert_eq!(right_trigger.first(), Some(&(0, 102)));
+    assert_eq!(right_trigger.last(), Some(&(80, 0)));
+    assert!(values(&timeline, "r2").is_empty());
+  }
+
+  #[test]
+  fn test_simulation_is_reproducible() {
+    let trace = "0,mouse,5,2\n3,mouse,7,1\n9,mouse,-4,0\n30,mouse,12,-8\n31,mouse,10,-6";
+
+    assert_eq!(run(trace), run(trace));
+  }
+}
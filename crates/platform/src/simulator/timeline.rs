@@ -0,0 +1,82 @@
+use std::{fmt::Write, path::Path};
+
+use serde::Serialize;
+
+use crate::simulator::{Format, SimulatorError};
+
+/// A change of one output of the virtual controller.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Sample {
+  /// Milliseconds since the start of the trace.
+  time: u64,
+  /// `left_x`, `left_y`, `right_x`, `right_y` or the name of a button.
+  output: String,
+  /// The stick position with positive values pointing right and up, or `1` for a pressed and `0`
+  /// for a released button.
+  value: i32,
+}
+
+impl Sample {
+  pub fn new(time: u64, output: impl Into<String>, value: i32) -> Self {
+    Self {
+      time,
+      output: output.into(),
+      value,
+    }
+  }
+
+  pub fn time(&self) -> u64 {
+    self.time
+  }
+
+  pub fn output(&self) -> &str {
+    &self.output
+  }
+
+  pub fn value(&self) -> i32 {
+    self.value
+  }
+}
+
+/// Everything the virtual controller emitted during a simulation, ordered by time.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[serde(transparent)]
+pub struct Timeline(Vec<Sample>);
+
+impl Timeline {
+  pub fn push(&mut self, sample: Sample) {
+    self.0.push(sample);
+  }
+
+  pub fn samples(&self) -> &[Sample] {
+    &self.0
+  }
+
+  /// The samples of a single output.
+  pub fn output<'a>(&'a self, output: &'a str) -> impl Iterator<Item = &'a Sample> {
+    self.0.iter().filter(move |sample| sample.output == output)
+  }
+
+  pub fn to_csv(&self) -> String {
+    let mut csv = String::from("time,output,value\n");
+    for sample in &self.0 {
+      writeln!(csv, "{},{},{}", sample.time, sample.output, sample.value).unwrap();
+    }
+    csv
+  }
+
+  pub fn to_json(&self) -> String {
+    serde_json::to_string_pretty(self).expect("failed to serialize timeline")
+  }
+
+  pub fn format(&self, format: Format) -> String {
+    match format {
+      Format::Csv => self.to_csv(),
+      Format::Json => self.to_json(),
+    }
+  }
+
+  pub fn write(&self, path: &Path, format: Format) -> Result<(), SimulatorError> {
+    std::fs::write(path, self.format(format)).map_err(|e| SimulatorError::Io(path.into(), e))
+  }
+}
@@ -0,0 +1,180 @@
+use std::{path::Path, str::FromStr};
+
+use controller::State;
+use io::Key;
+
+use serde::{Deserialize, Deserializer};
+
+use crate::simulator::{Format, SimulatorError};
+
+/// Something the user did at a point of a [`Trace`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Input {
+  /// The mouse moved by `dx` counts to the right and `dy` counts down.
+  Mouse {
+    dx: i32,
+    dy: i32,
+  },
+  /// A key or mouse button, named like in `bindings.toml`, was pressed or released.
+  Key {
+    #[serde(deserialize_with = "deserialize_key")]
+    key: Key,
+    #[serde(deserialize_with = "deserialize_state")]
+    state: State,
+  },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub struct TraceEvent {
+  /// Milliseconds since the start of the trace.
+  time: u64,
+  #[serde(flatten)]
+  input: Input,
+}
+
+impl TraceEvent {
+  pub fn new(time: u64, input: Input) -> Self {
+    Self {
+      time,
+      input,
+    }
+  }
+
+  pub fn time(&self) -> u64 {
+    self.time
+  }
+
+  pub fn input(&self) -> Input {
+    self.input
+  }
+}
+
+/// Recorded mouse movement and key events, ordered by time.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Trace(Vec<TraceEvent>);
+
+impl From<Vec<TraceEvent>> for Trace {
+  fn from(mut events: Vec<TraceEvent>) -> Self {
+    events.sort_by_key(TraceEvent::time);
+    Self(events)
+  }
+}
+
+impl Trace {
+  /// Reads a trace from a `.csv` or `.json` file.
+  pub fn read(path: &Path) -> Result<Self, SimulatorError> {
+    let format = Format::from_path(path).ok_or_else(|| SimulatorError::Format(path.into()))?;
+    let content = std::fs::read_to_string(path).map_err(|e| SimulatorError::Io(path.into(), e))?;
+    Self::parse(&content, format)
+  }
+
+  pub fn parse(content: &str, format: Format) -> Result<Self, SimulatorError> {
+    match format {
+      Format::Csv => Self::parse_csv(content),
+      Format::Json => serde_json::from_str::<Vec<TraceEvent>>(content)
+        .map(Self::from)
+        .map_err(|e| SimulatorError::Parse(e.line(), e.to_string())),
+    }
+  }
+
+  pub fn events(&self) -> &[TraceEvent] {
+    &self.0
+  }
+
+  /// Parses rows of `time,mouse,dx,dy` and `time,key,name,state`, skipping empty lines, comments
+  /// starting with `#` and a header row starting with `time`.
+  fn parse_csv(content: &str) -> Result<Self, SimulatorError> {
+    let mut events = Vec::new();
+
+    for (index, line) in content.lines().enumerate() {
+      let line = line.trim();
+      if line.is_empty() || line.starts_with('#') || line.starts_with("time") {
+        continue;
+      }
+
+      let error = |reason: String| SimulatorError::Parse(index + 1, reason);
+      let columns: Vec<&str> = line.split(',').map(str::trim).collect();
+      let [time, event, first, second] = columns[..] else {
+        return Err(error(format!("expected 4 columns, found {}", columns.len())));
+      };
+
+      let time = number(time).map_err(error)?;
+      let input = match event {
+        "mouse" => Input::Mouse {
+          dx: number(first).map_err(error)?,
+          dy: number(second).map_err(error)?,
+        },
+        "key" => Input::Key {
+          key: key(first).map_err(error)?,
+          state: state(second).map_err(error)?,
+        },
+        other => return Err(error(format!("unknown event '{other}'"))),
+      };
+
+      events.push(TraceEvent::new(time, input));
+    }
+
+    Ok(Self::from(events))
+  }
+}
+
+fn number<T: FromStr>(value: &str) -> Result<T, String> {
+  value.parse().map_err(|_| format!("'{value}' is not a valid number"))
+}
+
+fn key(name: &str) -> Result<Key, String> {
+  Key::try_from(name).map_err(|_| format!("unknown key '{name}'"))
+}
+
+fn state(name: &str) -> Result<State, String> {
+  match name {
+    "pressed" => Ok(State::Pressed),
+    "held" => Ok(State::Held),
+    "released" => Ok(State::Released),
+    other => Err(format!("unknown key state '{other}'")),
+  }
+}
+
+fn deserialize_key<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Key, D::Error> {
+  key(&String::deserialize(deserializer)?).map_err(serde::de::Error::custom)
+}
+
+fn deserialize_state<'de, D: Deserializer<'de>>(deserializer: D) -> Result<State, D::Error> {
+  state(&String::deserialize(deserializer)?).map_err(serde::de::Error::custom)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use io::AlphabeticKey;
+
+  #[test]
+  fn test_csv_and_json_traces_are_equivalent() {
+    let csv = "time,event,a,b\n# walk forward\n16,key,w,pressed\n0,mouse,3,-1\n";
+    let json = r#"[
+      {"time": 0, "event": "mouse", "dx": 3, "dy": -1},
+      {"time": 16, "event": "key", "key": "w", "state": "pressed"}
+    ]"#;
+
+    let csv = Trace::parse(csv, Format::Csv).unwrap();
+    let json = Trace::parse(json, Format::Json).unwrap();
+
+    assert_eq!(csv, json);
+    assert_eq!(
+      csv.events()[1].input(),
+      Input::Key {
+        key: Key::Alphabetic(AlphabeticKey::W),
+        state: State::Pressed
+      }
+    );
+  }
+
+  #[test]
+  fn test_csv_errors_name_the_line() {
+    let error = Trace::parse("0,mouse,1,1\n5,key,nope,pressed", Format::Csv).unwrap_err();
+
+    assert_eq!(error.to_string(), "invalid trace event on line 2: unknown key 'nope'");
+  }
+}
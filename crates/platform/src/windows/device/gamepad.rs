@@ -1,6 +1,6 @@
 use controller::{
   Axis, ButtonEvent, ControllerButton, ControllerError, ControllerEvent, JoyStick, JoyStickEvent,
-  JoyStickState, State,
+  State, Trigger, TriggerEvent,
 };
 use vigem_client::{XButtons, XGamepad};
 
@@ -22,16 +22,12 @@ impl Gamepad {
     self.handle
   }
 
-  pub fn update(
-    &mut self,
-    event: &ControllerEvent,
-    left_stick: &JoyStickState,
-    right_stick: &JoyStickState,
-  ) -> Result<(), ControllerError> {
+  pub fn update(&mut self, event: &ControllerEvent) -> Result<(), ControllerError> {
     use ControllerEvent::*;
     match event {
       Button(event) => self.handle_button_event(event),
-      JoyStick(event) => self.handle_joystick_event(event, left_stick, right_stick),
+      JoyStick(event) => self.handle_joystick_event(event),
+      Trigger(event) => self.handle_trigger_event(event),
     }
   }
 
@@ -42,30 +38,6 @@ impl Gamepad {
     let button = event.button();
     let state = event.state();
 
-    if button.eq(&L2) {
-      match state {
-        Pressed | Held => {
-          self.handle.left_trigger = 255;
-        }
-        _ => {
-          self.handle.left_trigger = 0;
-        }
-      };
-      return Ok(());
-    }
-
-    if button.eq(&R2) {
-      match state {
-        Pressed | Held => {
-          self.handle.right_trigger = 255;
-        }
-        _ => {
-          self.handle.right_trigger = 0;
-        }
-      };
-      return Ok(());
-    }
-
     let button_mask = match button {
       South => XButtons::A,
       East => XButtons::B,
@@ -82,6 +54,7 @@ impl Gamepad {
       Start => XButtons::START,
       Select => XButtons::BACK,
       Forward | Backward | Starboard | Port => 0,
+      // XInput has no trigger buttons, the triggers are pulled by trigger events.
       L2 | R2 => 0,
     };
 
@@ -97,29 +70,39 @@ impl Gamepad {
     Ok(())
   }
 
-  fn handle_joystick_event(
-    &mut self,
-    event: &JoyStickEvent,
-    left_stick: &JoyStickState,
-    right_stick: &JoyStickState,
-  ) -> Result<(), ControllerError> {
+  fn handle_joystick_event(&mut self, event: &JoyStickEvent) -> Result<(), ControllerError> {
     use Axis::*;
     use JoyStick::*;
 
     let joystick = event.joystick();
     let axis = event.axis();
+    // The events already carry the deadzone compensation and axis inversion, but the left stick's
+    // Y axis is emitted pointing down like evdev expects, while XInput points up.
+    let value = f64::from(event.polarity());
+    let thumb = |value: f64| value.clamp(i16::MIN.into(), i16::MAX.into()) as i16;
 
     match joystick {
       Left => match axis {
-        X => self.handle.thumb_lx = left_stick.vector().dx() as i16,
-        Y => self.handle.thumb_ly = left_stick.vector().dy() as i16,
+        X => self.handle.thumb_lx = thumb(value),
+        Y => self.handle.thumb_ly = thumb(-value),
       },
       Right => match axis {
-        X => self.handle.thumb_rx = right_stick.vector().dx() as i16,
-        Y => self.handle.thumb_ry = right_stick.vector().dy() as i16,
+        X => self.handle.thumb_rx = thumb(value),
+        Y => self.handle.thumb_ry = thumb(value),
       },
     };
 
     Ok(())
   }
+
+  fn handle_trigger_event(&mut self, event: &TriggerEvent) -> Result<(), ControllerError> {
+    let value = event.value().clamp(u8::MIN.into(), u8::MAX.into()) as u8;
+
+    match event.trigger() {
+      Trigger::Left => self.handle.left_trigger = value,
+      Trigger::Right => self.handle.right_trigger = value,
+    };
+
+    Ok(())
+  }
 }
@@ -1,6 +1,6 @@
 mod gamepad;
 
-use controller::{ControllerError, ControllerEvent, JoyStickState};
+use controller::{ControllerError, ControllerEvent};
 use vigem_client::{Client, TargetId, XTarget};
 
 use crate::windows::device::gamepad::Gamepad;
@@ -42,14 +42,9 @@ impl Default for VirtualDevice {
 }
 
 impl VirtualDevice {
-  pub fn emit(
-    &mut self,
-    events: &[ControllerEvent],
-    left_stick: &JoyStickState,
-    right_stick: &JoyStickState,
-  ) -> Result<(), ControllerError> {
+  pub fn emit(&mut self, events: &[ControllerEvent]) -> Result<(), ControllerError> {
     for event in events {
-      self.gamepad.update(event, left_stick, right_stick)?;
+      self.gamepad.update(event)?;
 
       if self.handle.update(&self.gamepad.handle()).is_err() {
         continue;
@@ -2,15 +2,17 @@ mod device;
 
 use crate::windows::device::VirtualDevice;
 
-use bindings::{JOYSTICK_KEYS, KEYBOARD_BUTTON_MAP};
+use bindings::BINDINGS;
 use controller::{
   Axis, ButtonEvent, ControllerError, ControllerEvent, ControllerEventEmitter, JoyStick,
   JoyStickEvent, JoyStickState, PlatformControllerManager, PlatformControllerOps, Polarity, State,
-  VirtualController, VirtualControllerCore,
+  TriggerState, VirtualController, VirtualControllerCore,
 };
 use io::{AlphabeticKey, ArrowKey, FunctionKey, Key, ModifierKey, MouseKey, NumericKey, SystemKey};
+use settings::SettingsHandle;
 
 use std::{
+  path::Path,
   sync::{mpsc::Sender, Arc, Mutex, OnceLock},
   time::Duration,
 };
@@ -41,15 +43,15 @@ static MOUSE_DELTA_TX: OnceLock<Sender<(i32, i32)>> = OnceLock::new();
 
 pub struct Controller {
   virtual_device: <WindowsOps as PlatformControllerOps>::VirtualDevice,
+  settings: SettingsHandle,
   left_stick: Arc<Mutex<JoyStickState>>,
   right_stick: Arc<Mutex<JoyStickState>>,
+  triggers: Mutex<TriggerState>,
 }
 
 impl ControllerEventEmitter for Controller {
   fn emit(&mut self, events: &[ControllerEvent]) -> Result<(), ControllerError> {
-    let left_stick = { self.left_stick.lock().unwrap().clone() };
-    let right_stick = { self.right_stick.lock().unwrap().clone() };
-    self.virtual_device.emit(events, &left_stick, &right_stick)
+    self.virtual_device.emit(events)
   }
 
   fn disconnect(&mut self) -> Result<(), ControllerError> {
@@ -58,6 +60,10 @@ impl ControllerEventEmitter for Controller {
 }
 
 impl VirtualController for Controller {
+  fn settings(&self) -> &SettingsHandle {
+    &self.settings
+  }
+
   fn left_stick(&self) -> &Mutex<JoyStickState> {
     &self.left_stick
   }
@@ -73,6 +79,10 @@ impl VirtualController for Controller {
   fn right_stick_mut(&mut self) -> &mut Arc<Mutex<JoyStickState>> {
     &mut self.right_stick
   }
+
+  fn triggers(&self) -> &Mutex<TriggerState> {
+    &self.triggers
+  }
 }
 
 pub struct WindowsOps;
@@ -112,12 +122,20 @@ impl PlatformControllerOps for WindowsOps {
       let mut controller = _g_key_down_controller.lock().unwrap();
       let state = Pressed;
       if let Some(key) = map_key(key) {
-        if JOYSTICK_KEYS.key_is_joystick_key(key) {
+        if crate::switch_profile(key, state, &mut *controller)
+          || crate::modify_left_stick(key, state, &mut *controller)
+        {
+          return;
+        }
+
+        let bindings = BINDINGS.load();
+        let joystick_keys = bindings.joystick_keys();
+        if joystick_keys.key_is_joystick_key(key) {
           let axis_polarity = match key {
-            k if JOYSTICK_KEYS.key_is_forward(k) => Some((Y, Positive(1))),
-            k if JOYSTICK_KEYS.key_is_backward(k) => Some((Y, Negative(1))),
-            k if JOYSTICK_KEYS.key_is_port(k) => Some((X, Negative(1))),
-            k if JOYSTICK_KEYS.key_is_starboard(k) => Some((X, Positive(1))),
+            k if joystick_keys.key_is_forward(k) => Some((Y, Positive(1))),
+            k if joystick_keys.key_is_backward(k) => Some((Y, Negative(1))),
+            k if joystick_keys.key_is_port(k) => Some((X, Negative(1))),
+            k if joystick_keys.key_is_starboard(k) => Some((X, Positive(1))),
             _ => None,
           };
 
@@ -125,7 +143,7 @@ impl PlatformControllerOps for WindowsOps {
             let event = ControllerEvent::from(JoyStickEvent::new(Left, axis, polarity, state));
             controller.handle_event(event).unwrap();
           }
-        } else if let Some(&button) = KEYBOARD_BUTTON_MAP.get(&key) {
+        } else if let Some(button) = bindings.button(&key) {
           let event = ControllerEvent::from(ButtonEvent::new(button, state));
           controller.handle_event(event).unwrap();
         }
@@ -137,12 +155,20 @@ impl PlatformControllerOps for WindowsOps {
       let mut controller = _g_key_up_controller.lock().unwrap();
       let state = Released;
       if let Some(key) = map_key(key) {
-        if JOYSTICK_KEYS.key_is_joystick_key(key) {
+        if crate::switch_profile(key, state, &mut *controller)
+          || crate::modify_left_stick(key, state, &mut *controller)
+        {
+          return;
+        }
+
+        let bindings = BINDINGS.load();
+        let joystick_keys = bindings.joystick_keys();
+        if joystick_keys.key_is_joystick_key(key) {
           let axis_polarity = match key {
-            k if JOYSTICK_KEYS.key_is_forward(k) => Some((Y, Positive(1))),
-            k if JOYSTICK_KEYS.key_is_backward(k) => Some((Y, Negative(1))),
-            k if JOYSTICK_KEYS.key_is_port(k) => Some((X, Negative(1))),
-            k if JOYSTICK_KEYS.key_is_starboard(k) => Some((X, Positive(1))),
+            k if joystick_keys.key_is_forward(k) => Some((Y, Positive(1))),
+            k if joystick_keys.key_is_backward(k) => Some((Y, Negative(1))),
+            k if joystick_keys.key_is_port(k) => Some((X, Negative(1))),
+            k if joystick_keys.key_is_starboard(k) => Some((X, Positive(1))),
             _ => None,
           };
 
@@ -150,8 +176,8 @@ impl PlatformControllerOps for WindowsOps {
             let event = ControllerEvent::from(JoyStickEvent::new(Left, axis, polarity, state));
             controller.handle_event(event).unwrap();
           }
-        } else if let Some(button) = KEYBOARD_BUTTON_MAP.get(&key) {
-          let button_event = ButtonEvent::new(*button, state);
+        } else if let Some(button) = bindings.button(&key) {
+          let button_event = ButtonEvent::new(button, state);
           let controller_event = ControllerEvent::from(button_event);
           controller.handle_event(controller_event).unwrap();
         }
@@ -163,8 +189,12 @@ impl PlatformControllerOps for WindowsOps {
       let mut controller = _g_mouse_down_controller.lock().unwrap();
       let state = Pressed;
       if let Some(key) = map_mouse_button(btn) {
-        if let Some(button) = KEYBOARD_BUTTON_MAP.get(&key) {
-          let button_event = ButtonEvent::new(*button, state);
+        if crate::modify_left_stick(key, state, &mut *controller) {
+          return;
+        }
+
+        if let Some(button) = BINDINGS.load().button(&key) {
+          let button_event = ButtonEvent::new(button, state);
           let controller_event = ControllerEvent::from(button_event);
           controller.handle_event(controller_event).unwrap();
         }
@@ -176,8 +206,12 @@ impl PlatformControllerOps for WindowsOps {
       let mut controller = _g_mouse_up_controller.lock().unwrap();
       let state = Released;
       if let Some(key) = map_mouse_button(btn) {
-        if let Some(button) = KEYBOARD_BUTTON_MAP.get(&key) {
-          let button_event = ButtonEvent::new(*button, state);
+        if crate::modify_left_stick(key, state, &mut *controller) {
+          return;
+        }
+
+        if let Some(button) = BINDINGS.load().button(&key) {
+          let button_event = ButtonEvent::new(button, state);
           let controller_event = ControllerEvent::from(button_event);
           controller.handle_event(controller_event).unwrap();
         }
@@ -365,13 +399,19 @@ fn map_key(key: &Keycode) -> Option<Key> {
 impl PlatformControllerManager for Controller {
   type Ops = WindowsOps;
 
-  fn try_create() -> Result<Self, Box<dyn std::error::Error>> {
+  fn try_create(settings: SettingsHandle) -> Result<Self, Box<dyn std::error::Error>> {
     Ok(Self {
       virtual_device: WindowsOps::create_virtual_controller().unwrap(),
-      left_stick: Arc::new(Mutex::new(JoyStickState::default())),
-      right_stick: Arc::new(Mutex::new(JoyStickState::default())),
+      left_stick: Arc::new(Mutex::new(JoyStickState::new(settings.clone()))),
+      right_stick: Arc::new(Mutex::new(JoyStickState::new(settings.clone()))),
+      triggers: Mutex::new(TriggerState::new(settings.clone())),
+      settings,
     })
   }
+
+  fn reload(path: &Path) -> Result<bool, Box<dyn std::error::Error>> {
+    crate::reload(path)
+  }
 }
 
 unsafe extern "system" fn wndproc(hwnd: HWND, msg: u32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
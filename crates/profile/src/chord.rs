@@ -0,0 +1,129 @@
+use controller::State;
+use io::Key;
+
+use std::collections::HashSet;
+
+/// A key combination that switches to `profile` once all of its keys are held.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Chord {
+  profile: String,
+  keys: Vec<Key>,
+}
+
+impl Chord {
+  pub fn new(profile: impl Into<String>, keys: &[Key]) -> Self {
+    Self {
+      profile: profile.into(),
+      keys: keys.iter().map(Key::normalize).collect(),
+    }
+  }
+
+  pub fn profile(&self) -> &str {
+    &self.profile
+  }
+
+  pub fn keys(&self) -> &[Key] {
+    &self.keys
+  }
+}
+
+#[derive(Debug, Default)]
+pub struct ChordTracker {
+  pressed: HashSet<Key>,
+  consumed: HashSet<Key>,
+}
+
+impl ChordTracker {
+  /// Records a key event and returns the profile whose chord was completed by it, if any.
+  ///
+  /// The keys of a completed chord are consumed until they are released.
+  pub fn update(&mut self, chords: &[Chord], key: Key, state: State) -> Option<String> {
+    let key = key.normalize();
+
+    match state {
+      State::Pressed => {
+        self.pressed.insert(key);
+        let chord = chords.iter().find(|chord| {
+          chord.keys.contains(&key) && chord.keys.iter().all(|k| self.pressed.contains(k))
+        })?;
+        self.consumed.extend(chord.keys.iter().copied());
+        Some(chord.profile.clone())
+      }
+      State::Held => None,
+      State::Released => {
+        self.pressed.remove(&key);
+        self.consumed.remove(&key);
+        None
+      }
+    }
+  }
+
+  /// Whether `key` belongs to a chord that was completed while it was held.
+  pub fn is_consumed(&self, key: Key) -> bool {
+    self.consumed.contains(&key.normalize())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use io::{ModifierKey, NumericKey};
+
+  #[test]
+  fn test_chord_completes_on_last_key() {
+    let ctrl = Key::Modifier(ModifierKey::LeftCtrl);
+    let one = Key::Numeric(NumericKey::Num1);
+    let chords = [Chord::new("shooter", &[ctrl, one])];
+    let mut tracker = ChordTracker::default();
+
+    assert_eq!(tracker.update(&chords, ctrl, State::Pressed), None);
+    assert_eq!(tracker.update(&chords, one, State::Pressed).as_deref(), Some("shooter"));
+    assert_eq!(tracker.update(&chords, one, State::Held), None);
+  }
+
+  #[test]
+  fn test_chord_keys_are_consumed_until_released() {
+    let ctrl = Key::Modifier(ModifierKey::LeftCtrl);
+    let one = Key::Numeric(NumericKey::Num1);
+    let chords = [Chord::new("shooter", &[ctrl, one])];
+    let mut tracker = ChordTracker::default();
+
+    tracker.update(&chords, ctrl, State::Pressed);
+
+    assert!(!tracker.is_consumed(ctrl));
+
+    tracker.update(&chords, one, State::Pressed);
+
+    assert!(tracker.is_consumed(ctrl));
+    assert!(tracker.is_consumed(one));
+
+    tracker.update(&chords, ctrl, State::Released);
+
+    assert!(!tracker.is_consumed(ctrl));
+    assert!(tracker.is_consumed(one));
+  }
+
+  #[test]
+  fn test_chord_requires_all_keys_held() {
+    let ctrl = Key::Modifier(ModifierKey::LeftCtrl);
+    let one = Key::Numeric(NumericKey::Num1);
+    let chords = [Chord::new("shooter", &[ctrl, one])];
+    let mut tracker = ChordTracker::default();
+
+    tracker.update(&chords, ctrl, State::Pressed);
+    tracker.update(&chords, ctrl, State::Released);
+    assert_eq!(tracker.update(&chords, one, State::Pressed), None);
+  }
+
+  #[test]
+  fn test_chord_matches_either_side_modifier() {
+    let ctrl = Key::Modifier(ModifierKey::Ctrl);
+    let right_ctrl = Key::Modifier(ModifierKey::RightCtrl);
+    let one = Key::Numeric(NumericKey::Num1);
+    let chords = [Chord::new("shooter", &[ctrl, one])];
+    let mut tracker = ChordTracker::default();
+
+    tracker.update(&chords, right_ctrl, State::Pressed);
+    assert_eq!(tracker.update(&chords, one, State::Pressed).as_deref(), Some("shooter"));
+  }
+}
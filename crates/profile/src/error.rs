@@ -0,0 +1,48 @@
+use settings::ConfigErrors;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProfileError {
+  NotFound(String),
+  InvalidName(String),
+  Cycle(Vec<String>),
+  Io(String, String),
+  Parse(String, String),
+  Write(String, String),
+  Config(ConfigErrors),
+}
+
+impl From<ConfigErrors> for ProfileError {
+  fn from(v: ConfigErrors) -> Self {
+    Self::Config(v)
+  }
+}
+
+impl std::fmt::Display for ProfileError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      ProfileError::NotFound(name) => {
+        write!(f, "profile '{}' does not exist", name)
+      }
+      ProfileError::InvalidName(name) => {
+        write!(f, "'{}' is not a valid profile name, it must not contain path separators", name)
+      }
+      ProfileError::Cycle(chain) => {
+        write!(f, "profile inheritance cycle: {}", chain.join(" -> "))
+      }
+      ProfileError::Io(name, reason) => {
+        write!(f, "failed to read profile '{}': {}", name, reason)
+      }
+      ProfileError::Parse(name, reason) => {
+        write!(f, "failed to parse profile '{}': {}", name, reason)
+      }
+      ProfileError::Write(path, reason) => {
+        write!(f, "failed to write {}: {}", path, reason)
+      }
+      ProfileError::Config(errors) => {
+        write!(f, "{}", errors)
+      }
+    }
+  }
+}
+
+impl std::error::Error for ProfileError {}
@@ -0,0 +1,409 @@
+mod chord;
+mod error;
+
+pub use chord::*;
+pub use error::*;
+
+use bindings::{Bindings, KeyMap, BINDINGS};
+use io::Key;
+use settings::{
+  report_upgrade, warn, Calibration, ConfigError, ConfigErrorReason, ConfigErrors,
+  ControllerSettings, Handle, Layer, Schema, CONFIG_VERSION, SETTINGS,
+};
+
+use std::{
+  path::{Path, PathBuf},
+  sync::{LazyLock, RwLock},
+};
+
+use serde::Deserialize;
+
+static ACTIVE: RwLock<Option<String>> = RwLock::new(None);
+
+/// The top-level fields of a profile file.
+const FIELDS: [&str; 5] = ["version", "inherits", "chord", "settings", "bindings"];
+
+pub static CHORDS: LazyLock<Handle<Vec<Chord>>> = LazyLock::new(Handle::default);
+
+pub fn profiles_dir() -> Option<PathBuf> {
+  settings::config_dir().map(|dir| dir.join("profiles"))
+}
+
+/// A named set of overrides for `joymouse.toml` and `bindings.toml`.
+///
+/// Profiles live in `profiles/<name>.toml` and only need to contain the fields that differ from
+/// the profile they inherit from, or from the base configuration files if they inherit nothing.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "snake_case")]
+pub struct Profile {
+  inherits: Option<String>,
+  chord: Vec<String>,
+  settings: toml::Table,
+  bindings: toml::Table,
+  /// The schema version the file was written for.
+  #[serde(skip)]
+  version: u32,
+  /// Fields of the file that are not used.
+  #[serde(skip)]
+  unknown: Vec<String>,
+}
+
+impl Profile {
+  pub fn read(name: &str) -> Result<Self, ProfileError> {
+    if !Self::is_valid_name(name) {
+      return Err(ProfileError::InvalidName(name.to_owned()));
+    }
+
+    let path = Self::path(name).ok_or_else(|| ProfileError::NotFound(name.to_owned()))?;
+
+    if !path.exists() {
+      return Err(ProfileError::NotFound(name.to_owned()));
+    }
+
+    let content = std::fs::read_to_string(&path)
+      .map_err(|e| ProfileError::Io(name.to_owned(), e.to_string()))?;
+
+    let mut table = toml::from_str::<toml::Table>(&content)
+      .map_err(|e| ProfileError::Parse(name.to_owned(), e.to_string()))?;
+
+    let origin = Self::origin(name);
+    let version = Self::upgrade(&origin, &mut table).map_err(ConfigErrors::from)?;
+
+    let mut unknown: Vec<String> =
+      table.keys().filter(|key| !FIELDS.contains(&key.as_str())).cloned().collect();
+    if let Some(toml::Value::Table(settings)) = table.get("settings") {
+      unknown
+        .extend(settings::unknown_settings(settings).iter().map(|key| format!("settings.{key}")));
+    }
+
+    let mut profile =
+      Self::deserialize(table).map_err(|e| ProfileError::Parse(name.to_owned(), e.to_string()))?;
+    profile.version = version;
+    profile.unknown = unknown;
+    Ok(profile)
+  }
+
+  /// Logs the migrations and unknown fields found while reading profile `name`.
+  fn report(&self, name: &str) {
+    let origin = Self::origin(name);
+    report_upgrade(&origin, self.version);
+    for key in &self.unknown {
+      warn(&ConfigError::new(&origin, Some(key), ConfigErrorReason::UnknownField));
+    }
+  }
+
+  /// Upgrades the `[settings]` and `[bindings]` tables of a profile file to [`CONFIG_VERSION`].
+  fn upgrade(origin: &str, table: &mut toml::Table) -> Result<u32, ConfigError> {
+    let version = settings::version(origin, table)?;
+
+    for (key, schema) in [("settings", Schema::Settings), ("bindings", Schema::Bindings)] {
+      if let Some(toml::Value::Table(section)) = table.get_mut(key) {
+        settings::migrate(section, schema, version);
+      }
+    }
+
+    table.insert("version".into(), toml::Value::Integer(CONFIG_VERSION.into()));
+    Ok(version)
+  }
+
+  /// Returns where profile `name` is stored, or `None` if the name would leave the profiles
+  /// directory.
+  pub fn path(name: &str) -> Option<PathBuf> {
+    if !Self::is_valid_name(name) {
+      return None;
+    }
+    profiles_dir().map(|dir| dir.join(format!("{name}.toml")))
+  }
+
+  /// Whether `name` names a file directly inside the profiles directory.
+  fn is_valid_name(name: &str) -> bool {
+    !name.is_empty() && !name.contains(['/', '\\']) && !name.contains("..")
+  }
+
+  pub fn inherits(&self) -> Option<&str> {
+    self.inherits.as_deref()
+  }
+
+  pub fn chord(&self, name: &str) -> Result<Vec<Key>, ProfileError> {
+    let mut errors = ConfigErrors::default();
+    let mut keys = Vec::with_capacity(self.chord.len());
+
+    for key in &self.chord {
+      match Key::try_from(key.as_str()) {
+        Ok(key) => keys.push(key),
+        Err(_) => errors.push(ConfigError::new(
+          Self::origin(name),
+          Some("chord"),
+          ConfigErrorReason::UnknownKey(key.to_owned()),
+        )),
+      }
+    }
+
+    Ok(errors.into_result(keys)?)
+  }
+
+  fn settings(&self, name: &str) -> Layer {
+    Layer::new(Self::origin(name), self.settings.clone())
+  }
+
+  fn bindings(&self, name: &str) -> Result<Bindings, ConfigErrors> {
+    Bindings::parse(&Self::origin(name), &self.bindings)
+  }
+
+  fn origin(name: &str) -> String {
+    Self::path(name).map(|path| path.display().to_string()).unwrap_or_else(|| name.to_owned())
+  }
+}
+
+/// Returns the names of every profile in the profiles directory.
+pub fn list() -> Vec<String> {
+  let Some(entries) = profiles_dir().and_then(|dir| std::fs::read_dir(dir).ok()) else {
+    return Vec::new();
+  };
+
+  let mut names: Vec<String> = entries
+    .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+    .filter(|path| path.extension().is_some_and(|ext| ext == "toml"))
+    .filter_map(|path| path.file_stem().and_then(|stem| stem.to_str()).map(str::to_owned))
+    .collect();
+
+  names.sort();
+  names
+}
+
+pub fn active() -> Option<String> {
+  ACTIVE.read().unwrap().clone()
+}
+
+/// Loads the profile `name` and its ancestors, starting with the one furthest up the chain.
+fn chain(name: &str) -> Result<Vec<(String, Profile)>, ProfileError> {
+  let mut chain: Vec<(String, Profile)> = Vec::new();
+  let mut next = Some(name.to_owned());
+
+  while let Some(name) = next {
+    if chain.iter().any(|(other, _)| *other == name) {
+      let mut cycle: Vec<String> = chain.into_iter().map(|(name, _)| name).collect();
+      cycle.push(name);
+      return Err(ProfileError::Cycle(cycle));
+    }
+
+    let profile = Profile::read(&name)?;
+    profile.report(&name);
+    next = profile.inherits.clone();
+    chain.push((name, profile));
+  }
+
+  chain.reverse();
+  Ok(chain)
+}
+
+/// Builds the settings and bindings of profile `name` on top of the base configuration files.
+///
+/// Without a name, only the base configuration files are loaded.
+pub fn resolve(name: Option<&str>) -> Result<(ControllerSettings, Bindings), ProfileError> {
+  let chain = match name {
+    Some(name) => chain(name)?,
+    None => Vec::new(),
+  };
+
+  let mut errors = ConfigErrors::default();
+
+  let layers: Vec<Layer> = chain.iter().map(|(name, profile)| profile.settings(name)).collect();
+  let settings = settings::load_with(&layers).map_err(|e| errors.extend(e)).ok();
+
+  let mut bindings = bindings::load().map_err(|e| errors.extend(e)).ok();
+  for (name, profile) in &chain {
+    match (profile.bindings(name), bindings.as_mut()) {
+      (Ok(overrides), Some(bindings)) => bindings.merge(overrides),
+      (Ok(_), None) => {}
+      (Err(e), _) => errors.extend(e),
+    }
+  }
+
+  match (settings, bindings) {
+    (Some(settings), Some(bindings)) if errors.is_empty() => Ok((settings, bindings)),
+    _ => Err(ProfileError::Config(errors)),
+  }
+}
+
+/// Swaps the settings and bindings of profile `name` into [`SETTINGS`] and [`BINDINGS`].
+///
+/// Nothing changes if the profile cannot be resolved.
+pub fn activate(name: Option<&str>) -> Result<(), ProfileError> {
+  let (settings, bindings) = resolve(name)?;
+  SETTINGS.store(settings);
+  BINDINGS.store(KeyMap::from(bindings));
+  CHORDS.store(chords());
+  *ACTIVE.write().unwrap() = name.map(str::to_owned);
+  Ok(())
+}
+
+/// Re-reads the configuration files of the active profile.
+pub fn reload() -> Result<(), ProfileError> {
+  activate(active().as_deref())
+}
+
+/// Stores `calibration` in the active profile, or in `joymouse.toml` if no profile is active.
+///
+/// Only the `calibration` table is replaced, the rest of the file is kept as it is. Returns the
+/// path of the file that was written.
+pub fn store_calibration(calibration: &Calibration) -> Result<PathBuf, ProfileError> {
+  let active = active();
+  let path = match &active {
+    Some(name) => Profile::path(name),
+    None => settings::config_path(),
+  }
+  .ok_or_else(|| {
+    let file = active.as_deref().map_or("joymouse.toml".to_owned(), |name| format!("{name}.toml"));
+    ProfileError::Write(file, "failed to find config directory".into())
+  })?;
+  let origin = path.display().to_string();
+  let error = |reason| ConfigErrors::from(ConfigError::new(&origin, None, reason));
+
+  let content =
+    std::fs::read_to_string(&path).map_err(|e| error(ConfigErrorReason::Io(e.to_string())))?;
+  let content = with_calibration(&content, active.is_some(), calibration)
+    .map_err(|e| error(ConfigErrorReason::Syntax(e.to_string())))?;
+
+  std::fs::write(&path, content).map_err(|e| ProfileError::Write(origin.clone(), e.to_string()))?;
+  Ok(path)
+}
+
+/// Replaces the `calibration` table of the configuration file `content`, or of its `[settings]`
+/// table for a profile, keeping the comments and layout of everything else.
+fn with_calibration(
+  content: &str,
+  profile: bool,
+  calibration: &Calibration,
+) -> Result<String, toml_edit::TomlError> {
+  let mut document: toml_edit::DocumentMut = content.parse()?;
+  let serialized = toml::to_string(calibration).expect("failed to serialize calibration");
+  let mut table = serialized.parse::<toml_edit::DocumentMut>()?.as_table().clone();
+
+  let section = if profile {
+    let settings = document.entry("settings").or_insert_with(|| {
+      let mut settings = toml_edit::Table::new();
+      settings.set_implicit(true);
+      settings.into()
+    });
+    if !settings.is_table() {
+      *settings = toml_edit::Table::new().into();
+    }
+    settings.as_table_mut().unwrap()
+  } else {
+    document.as_table_mut()
+  };
+
+  if let Some(previous) = section.get("calibration").and_then(toml_edit::Item::as_table) {
+    *table.decor_mut() = previous.decor().clone();
+  }
+  section.insert("calibration", table.into());
+
+  Ok(document.to_string())
+}
+
+fn chords() -> Vec<Chord> {
+  list()
+    .into_iter()
+    .filter_map(|name| match Profile::read(&name).and_then(|profile| profile.chord(&name)) {
+      Ok(keys) if !keys.is_empty() => Some(Chord::new(name, &keys)),
+      Ok(_) => None,
+      Err(e) => {
+        eprintln!("Ignoring chord of profile '{name}': {e}");
+        None
+      }
+    })
+    .collect()
+}
+
+/// Upgrades a configuration file in memory and returns the version it was written for.
+type Upgrade = fn(&str, &mut toml::Table) -> Result<u32, ConfigError>;
+
+/// Rewrites every configuration file that was written for an older schema version.
+///
+/// The previous version of each file is kept next to it with a `.bak` suffix. Returns the paths of
+/// the rewritten files.
+pub fn migrate() -> Result<Vec<PathBuf>, ConfigErrors> {
+  let mut errors = ConfigErrors::default();
+  let mut migrated = Vec::new();
+
+  let mut files: Vec<(Option<PathBuf>, Upgrade)> = vec![
+    (settings::config_path(), |origin, table| settings::upgrade(origin, table, Schema::Settings)),
+    (bindings::bindings_path(), |origin, table| settings::upgrade(origin, table, Schema::Bindings)),
+  ];
+  files.extend(list().into_iter().map(|name| (Profile::path(&name), Profile::upgrade as Upgrade)));
+
+  for (path, upgrade) in files {
+    let Some(path) = path.filter(|path| path.exists()) else {
+      continue;
+    };
+
+    match migrate_file(&path, upgrade) {
+      Ok(true) => migrated.push(path),
+      Ok(false) => {}
+      Err(e) => errors.push(e),
+    }
+  }
+
+  errors.into_result(migrated)
+}
+
+fn migrate_file(path: &Path, upgrade: Upgrade) -> Result<bool, ConfigError> {
+  let mut table = settings::read_table(path)?;
+
+  if upgrade(&path.display().to_string(), &mut table)? == CONFIG_VERSION {
+    return Ok(false);
+  }
+
+  settings::write_upgraded(path, &table)?;
+  Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_profile_names_must_stay_in_the_profiles_directory() {
+    for name in ["../joymouse", "nested/profile", "..\\profile", ".."] {
+      assert_eq!(Profile::read(name).unwrap_err(), ProfileError::InvalidName(name.to_owned()));
+      assert_eq!(Profile::path(name), None);
+    }
+
+    assert!(Profile::path("shooter").is_some_and(|path| path.ends_with("profiles/shooter.toml")));
+  }
+
+  #[test]
+  fn test_storing_a_calibration_keeps_comments() {
+    let calibration = Calibration::new(vec![[0.0, 0.0], [0.5, 0.25], [1.0, 1.0]]);
+    let content = "# Mouse feel\nsensitivity = 3.0\n\n# Measured on 2024-01-01\n[calibration]\n\
+                   points = [[0.0, 0.0], [1.0, 1.0]]\n";
+
+    let updated = with_calibration(content, false, &calibration).unwrap();
+
+    assert!(updated.starts_with("# Mouse feel\nsensitivity = 3.0\n\n# Measured on 2024-01-01\n"));
+    let table: toml::Table = toml::from_str(&updated).unwrap();
+    assert_eq!(table["calibration"], toml::Value::try_from(&calibration).unwrap());
+  }
+
+  #[test]
+  fn test_storing_a_calibration_in_a_profile_nests_it_under_settings() {
+    let calibration = Calibration::default();
+    let content = "# Sniper\ninherits = \"shooter\"\n";
+
+    let updated = with_calibration(content, true, &calibration).unwrap();
+
+    assert!(updated.starts_with(content));
+    let table: toml::Table = toml::from_str(&updated).unwrap();
+    assert_eq!(table["settings"]["calibration"], toml::Value::try_from(&calibration).unwrap());
+  }
+
+  #[test]
+  fn test_failed_activation_keeps_previous_settings() {
+    let previous = ControllerSettings::from_toml("test", "sensitivity = 3.5").unwrap();
+    SETTINGS.store(previous);
+
+    assert!(activate(Some("does-not-exist")).is_err());
+    assert_eq!(SETTINGS.load().sensitivity(), 3.5);
+  }
+}
@@ -0,0 +1,49 @@
+use crate::ConfigErrorReason;
+
+use serde::{Deserialize, Serialize};
+
+/// Configuration of the `absolute` mapper, which moves the stick like a virtual joystick.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct Absolute {
+  radius: f64,
+  spring: f64,
+}
+
+impl Default for Absolute {
+  fn default() -> Self {
+    Self {
+      radius: 400.0,
+      spring: 0.0,
+    }
+  }
+}
+
+impl Absolute {
+  /// How many mouse counts away from the center the stick reaches a full tilt.
+  pub fn radius(&self) -> f64 {
+    self.radius
+  }
+
+  /// How many mouse counts per second the stick is pulled back towards the center, `0.0` holds it.
+  pub fn spring(&self) -> f64 {
+    self.spring
+  }
+
+  /// Returns every field whose value does not make sense, together with the reason.
+  pub fn validate(&self) -> Vec<(&'static str, ConfigErrorReason)> {
+    use ConfigErrorReason::*;
+
+    let mut errors = Vec::new();
+
+    if self.radius <= 0.0 {
+      errors.push(("absolute.radius", NotPositive));
+    }
+
+    if self.spring < 0.0 {
+      errors.push(("absolute.spring", AtLeast(0.0)));
+    }
+
+    errors
+  }
+}
@@ -0,0 +1,191 @@
+use crate::ConfigErrorReason;
+
+use serde::{Deserialize, Serialize};
+
+/// Why measurements could not be turned into a [`Calibration`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum CalibrationError {
+  /// Fewer than two tilts were measured.
+  NotEnoughSteps,
+  /// A tilt outside of `0.0..=1.0` or a negative angle was measured.
+  InvalidMeasurement(f64, f64),
+  /// The camera did not turn at any tilt.
+  NoResponse,
+}
+
+impl std::fmt::Display for CalibrationError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      CalibrationError::NotEnoughSteps => write!(f, "at least two tilts have to be measured"),
+      CalibrationError::InvalidMeasurement(tilt, degrees) => {
+        write!(f, "turning {} degrees at a tilt of {} is not a valid measurement", degrees, tilt)
+      }
+      CalibrationError::NoResponse => write!(f, "the camera did not turn at any tilt"),
+    }
+  }
+}
+
+impl std::error::Error for CalibrationError {}
+
+/// An inverse lookup table that linearizes how fast a game turns the camera for a right stick
+/// tilt.
+///
+/// Each point maps a share of the maximum turn rate onto the tilt that produces it, both from
+/// `0.0` to `1.0`. Values in between are interpolated linearly.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct Calibration {
+  points: Vec<[f64; 2]>,
+}
+
+impl Default for Calibration {
+  fn default() -> Self {
+    Self {
+      points: vec![[0.0, 0.0], [1.0, 1.0]],
+    }
+  }
+}
+
+impl Calibration {
+  pub fn new(points: Vec<[f64; 2]>) -> Self {
+    Self {
+      points,
+    }
+  }
+
+  pub fn points(&self) -> &[[f64; 2]] {
+    &self.points
+  }
+
+  /// Fits the table from how many degrees the camera turned while the stick was held at each
+  /// tilt for the same amount of time.
+  ///
+  /// The largest angle is taken as the maximum turn rate. Tilts at which the camera did not turn
+  /// faster than at a smaller tilt are skipped, so the table only ever increases.
+  pub fn fit(measurements: &[(f64, f64)]) -> Result<Self, CalibrationError> {
+    if measurements.len() < 2 {
+      return Err(CalibrationError::NotEnoughSteps);
+    }
+
+    if let Some(&(tilt, degrees)) = measurements
+      .iter()
+      .find(|(tilt, degrees)| !(0.0..=1.0).contains(tilt) || degrees.is_nan() || *degrees < 0.0)
+    {
+      return Err(CalibrationError::InvalidMeasurement(tilt, degrees));
+    }
+
+    let max = measurements.iter().map(|(_, degrees)| *degrees).fold(0.0, f64::max);
+    if max <= 0.0 {
+      return Err(CalibrationError::NoResponse);
+    }
+
+    let mut measurements = measurements.to_vec();
+    measurements.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    // The camera does not move at all within the deadzone of the game, so the smallest movement
+    // needs the largest tilt that still does not turn it.
+    let start = measurements
+      .iter()
+      .filter(|(_, degrees)| *degrees == 0.0)
+      .map(|(tilt, _)| *tilt)
+      .fold(0.0, f64::max);
+    let mut points = vec![[0.0, start]];
+
+    for (tilt, degrees) in measurements {
+      let response = degrees / max;
+      let [previous_response, previous_tilt] = *points.last().unwrap();
+      if response > previous_response && tilt > previous_tilt {
+        points.push([response, tilt]);
+      }
+    }
+
+    Ok(Self::new(points))
+  }
+
+  /// Maps the share of the maximum turn rate in `0..=1` onto the tilt that produces it.
+  pub fn apply(&self, response: f64) -> f64 {
+    let x = response.clamp(0.0, 1.0);
+
+    let Some(first) = self.points.first() else {
+      return x;
+    };
+
+    if x <= first[0] {
+      return first[1];
+    }
+
+    for pair in self.points.windows(2) {
+      let ([x0, y0], [x1, y1]) = (pair[0], pair[1]);
+      if x <= x1 {
+        return y0 + (y1 - y0) * (x - x0) / (x1 - x0);
+      }
+    }
+
+    self.points.last().map_or(x, |last| last[1])
+  }
+
+  /// Returns every field whose value does not make sense, together with the reason.
+  pub fn validate(&self) -> Vec<(&'static str, ConfigErrorReason)> {
+    use ConfigErrorReason::*;
+
+    let mut errors = Vec::new();
+
+    if self.points.len() < 2 {
+      errors.push(("calibration.points", Invalid("needs at least two points".into())));
+    }
+
+    if self.points.iter().flatten().any(|value| !(0.0..=1.0).contains(value)) {
+      errors.push(("calibration.points", OutOfRange(0.0, 1.0)));
+    }
+
+    if self.points.windows(2).any(|pair| pair[0][0] >= pair[1][0] || pair[0][1] > pair[1][1]) {
+      errors.push(("calibration.points", Invalid("values must be increasing".into())));
+    }
+
+    errors
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn measure(game: impl Fn(f64) -> f64) -> Vec<(f64, f64)> {
+    (1..=10).map(|step| step as f64 / 10.0).map(|tilt| (tilt, game(tilt) * 180.0)).collect()
+  }
+
+  #[test]
+  fn test_default_calibration_is_identity() {
+    let calibration = Calibration::default();
+
+    assert_eq!(calibration.apply(0.3), 0.3);
+    assert!(calibration.validate().is_empty());
+  }
+
+  #[test]
+  fn test_fit_inverts_the_response_of_the_game() {
+    let calibration = Calibration::fit(&measure(|tilt| tilt * tilt)).unwrap();
+
+    assert!((calibration.apply(0.25) - 0.5).abs() < 0.02);
+    assert_eq!(calibration.apply(1.0), 1.0);
+    assert!(calibration.validate().is_empty());
+  }
+
+  #[test]
+  fn test_fit_skips_the_deadzone_of_the_game() {
+    let calibration = Calibration::fit(&measure(|tilt| ((tilt - 0.25) / 0.75).max(0.0))).unwrap();
+
+    assert_eq!(calibration.points()[0], [0.0, 0.2]);
+    assert!(calibration.apply(0.01) > 0.2);
+  }
+
+  #[test]
+  fn test_fit_rejects_useless_measurements() {
+    assert_eq!(Calibration::fit(&[(1.0, 90.0)]), Err(CalibrationError::NotEnoughSteps));
+    assert_eq!(Calibration::fit(&[(0.5, 0.0), (1.0, 0.0)]), Err(CalibrationError::NoResponse));
+    assert_eq!(
+      Calibration::fit(&[(0.5, -1.0), (1.0, 90.0)]),
+      Err(CalibrationError::InvalidMeasurement(0.5, -1.0))
+    );
+  }
+}
@@ -0,0 +1,232 @@
+use crate::ConfigErrorReason;
+
+use serde::{Deserialize, Serialize};
+
+/// The shape of a [`ResponseCurve`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CurveKind {
+  /// The tilt grows proportionally with the speed.
+  #[default]
+  Linear,
+  /// `speed ^ exponent`, i.e. slow movements stay precise while fast ones ramp up quickly.
+  Power,
+  /// Flat around both ends and steep in the middle, shaped by `steepness`.
+  SCurve,
+  /// Linear interpolation between the user-defined `points`.
+  Points,
+  /// A cubic bézier from `(0, 0)` to `(1, 1)` with the control points `control`, like CSS easing.
+  Bezier,
+}
+
+/// Maps the normalized mouse speed onto the normalized tilt of the right stick.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct ResponseCurve {
+  kind: CurveKind,
+  exponent: f64,
+  steepness: f64,
+  points: Vec<[f64; 2]>,
+  control: [f64; 4],
+}
+
+impl Default for ResponseCurve {
+  fn default() -> Self {
+    Self {
+      kind: CurveKind::Linear,
+      exponent: 2.0,
+      steepness: 2.0,
+      points: vec![[0.0, 0.0], [1.0, 1.0]],
+      control: [0.42, 0.0, 0.58, 1.0],
+    }
+  }
+}
+
+impl ResponseCurve {
+  pub fn new(kind: CurveKind) -> Self {
+    Self {
+      kind,
+      ..Default::default()
+    }
+  }
+
+  pub fn with_exponent(mut self, exponent: f64) -> Self {
+    self.exponent = exponent;
+    self
+  }
+
+  pub fn with_steepness(mut self, steepness: f64) -> Self {
+    self.steepness = steepness;
+    self
+  }
+
+  pub fn with_points(mut self, points: Vec<[f64; 2]>) -> Self {
+    self.points = points;
+    self
+  }
+
+  pub fn with_control(mut self, control: [f64; 4]) -> Self {
+    self.control = control;
+    self
+  }
+
+  pub fn kind(&self) -> CurveKind {
+    self.kind
+  }
+
+  /// Maps `speed` in `0..=1` onto a tilt in `0..=1`.
+  pub fn apply(&self, speed: f64) -> f64 {
+    let x = speed.clamp(0.0, 1.0);
+
+    let y = match self.kind {
+      CurveKind::Linear => x,
+      CurveKind::Power => x.powf(self.exponent),
+      CurveKind::SCurve => {
+        let rise = x.powf(self.steepness);
+        let fall = (1.0 - x).powf(self.steepness);
+        rise / (rise + fall)
+      }
+      CurveKind::Points => self.interpolate(x),
+      CurveKind::Bezier => self.bezier(x),
+    };
+
+    y.clamp(0.0, 1.0)
+  }
+
+  fn interpolate(&self, x: f64) -> f64 {
+    let Some(first) = self.points.first() else {
+      return x;
+    };
+
+    if x <= first[0] {
+      return first[1];
+    }
+
+    for pair in self.points.windows(2) {
+      let ([x0, y0], [x1, y1]) = (pair[0], pair[1]);
+      if x <= x1 {
+        return y0 + (y1 - y0) * (x - x0) / (x1 - x0);
+      }
+    }
+
+    self.points.last().map_or(x, |last| last[1])
+  }
+
+  fn bezier(&self, x: f64) -> f64 {
+    let [x1, y1, x2, y2] = self.control;
+    let cubic = |t: f64, p1: f64, p2: f64| {
+      let u = 1.0 - t;
+      3.0 * u * u * t * p1 + 3.0 * u * t * t * p2 + t * t * t
+    };
+
+    // x(t) is monotonic because both control points lie within 0..=1, so bisection finds the t
+    // that belongs to x.
+    let (mut low, mut high) = (0.0, 1.0);
+    for _ in 0..32 {
+      let t = (low + high) / 2.0;
+      if cubic(t, x1, x2) < x {
+        low = t;
+      } else {
+        high = t;
+      }
+    }
+
+    cubic((low + high) / 2.0, y1, y2)
+  }
+
+  /// Returns every field whose value does not make sense, together with the reason.
+  pub fn validate(&self) -> Vec<(&'static str, ConfigErrorReason)> {
+    use ConfigErrorReason::*;
+
+    let mut errors = Vec::new();
+
+    if self.exponent <= 0.0 {
+      errors.push(("curve.exponent", NotPositive));
+    }
+
+    if self.steepness <= 0.0 {
+      errors.push(("curve.steepness", NotPositive));
+    }
+
+    if self.points.len() < 2 {
+      errors.push(("curve.points", Invalid("needs at least two points".into())));
+    }
+
+    if self.points.iter().flatten().any(|value| !(0.0..=1.0).contains(value)) {
+      errors.push(("curve.points", OutOfRange(0.0, 1.0)));
+    }
+
+    if self.points.windows(2).any(|pair| pair[0][0] >= pair[1][0]) {
+      errors.push(("curve.points", Invalid("x values must be strictly increasing".into())));
+    }
+
+    if self.control.iter().any(|value| !(0.0..=1.0).contains(value)) {
+      errors.push(("curve.control", OutOfRange(0.0, 1.0)));
+    }
+
+    errors
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn assert_close(a: f64, b: f64) {
+    assert!((a - b).abs() < 1e-6, "{a} != {b}");
+  }
+
+  #[test]
+  fn test_linear_is_identity() {
+    let curve = ResponseCurve::default();
+
+    assert_close(curve.apply(0.25), 0.25);
+    assert_close(curve.apply(1.5), 1.0);
+    assert_close(curve.apply(-1.0), 0.0);
+  }
+
+  #[test]
+  fn test_power_curve() {
+    let curve = ResponseCurve::new(CurveKind::Power).with_exponent(2.0);
+
+    assert_close(curve.apply(0.5), 0.25);
+    assert_close(curve.apply(1.0), 1.0);
+  }
+
+  #[test]
+  fn test_s_curve_is_symmetric() {
+    let curve = ResponseCurve::new(CurveKind::SCurve).with_steepness(3.0);
+
+    assert_close(curve.apply(0.5), 0.5);
+    assert!(curve.apply(0.2) < 0.2);
+    assert!(curve.apply(0.8) > 0.8);
+    assert_close(curve.apply(0.2) + curve.apply(0.8), 1.0);
+  }
+
+  #[test]
+  fn test_points_interpolate_linearly() {
+    let curve =
+      ResponseCurve::new(CurveKind::Points).with_points(vec![[0.0, 0.0], [0.5, 0.2], [1.0, 1.0]]);
+
+    assert_close(curve.apply(0.25), 0.1);
+    assert_close(curve.apply(0.75), 0.6);
+    assert_close(curve.apply(1.0), 1.0);
+  }
+
+  #[test]
+  fn test_bezier_with_linear_control_points() {
+    let curve = ResponseCurve::new(CurveKind::Bezier).with_control([0.25, 0.25, 0.75, 0.75]);
+
+    assert_close(curve.apply(0.3), 0.3);
+    assert_close(curve.apply(0.9), 0.9);
+  }
+
+  #[test]
+  fn test_validate_points() {
+    let curve = ResponseCurve::new(CurveKind::Points).with_points(vec![[0.5, 0.0], [0.2, 1.5]]);
+
+    let keys: Vec<&str> = curve.validate().into_iter().map(|(key, _)| key).collect();
+
+    assert_eq!(keys, ["curve.points", "curve.points"]);
+  }
+}
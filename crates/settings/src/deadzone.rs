@@ -0,0 +1,196 @@
+use crate::{ConfigErrorReason, MAX_STICK_TILT};
+
+use serde::{Deserialize, Serialize};
+
+/// The shape of the inner deadzone a game applies to a stick.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeadzoneShape {
+  /// Ignores the stick while its distance from the center is below `inner`.
+  #[default]
+  Radial,
+  /// Ignores each axis on its own while it is below `inner`, which forms a cross.
+  Axial,
+  /// Ignores the stick while both axes are below `inner`.
+  Square,
+}
+
+/// Compensation for the deadzone a game applies to one stick.
+///
+/// Every tilt is remapped so that the smallest movement lands just outside of the inner deadzone
+/// and a full tilt lands on the `outer` saturation point.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct Deadzone {
+  shape: DeadzoneShape,
+  inner: f64,
+  outer: f64,
+}
+
+impl Default for Deadzone {
+  fn default() -> Self {
+    Self {
+      shape: DeadzoneShape::Radial,
+      inner: 0.0,
+      outer: 1.0,
+    }
+  }
+}
+
+impl Deadzone {
+  pub fn new(shape: DeadzoneShape, inner: f64, outer: f64) -> Self {
+    Self {
+      shape,
+      inner,
+      outer,
+    }
+  }
+
+  pub fn shape(&self) -> DeadzoneShape {
+    self.shape
+  }
+
+  pub fn inner(&self) -> f64 {
+    self.inner
+  }
+
+  pub fn outer(&self) -> f64 {
+    self.outer
+  }
+
+  /// Remaps a stick position given in stick units onto the live zone of the game.
+  pub fn apply(&self, x: f64, y: f64) -> (f64, f64) {
+    let (x, y) = (x / MAX_STICK_TILT, y / MAX_STICK_TILT);
+    let remap = |value: f64| self.inner + (self.outer - self.inner) * value;
+
+    let (x, y) = match self.shape {
+      DeadzoneShape::Radial => scale(x, y, x.hypot(y), remap),
+      DeadzoneShape::Square => scale(x, y, x.abs().max(y.abs()), remap),
+      DeadzoneShape::Axial => {
+        let axis = |value: f64| {
+          if is_centered(value.abs()) {
+            0.0
+          } else {
+            remap(value.abs()).copysign(value)
+          }
+        };
+        (axis(x), axis(y))
+      }
+    };
+
+    (x * MAX_STICK_TILT, y * MAX_STICK_TILT)
+  }
+
+  fn validate(
+    &self,
+    inner: &'static str,
+    outer: &'static str,
+  ) -> Vec<(&'static str, ConfigErrorReason)> {
+    use ConfigErrorReason::*;
+
+    let mut errors = Vec::new();
+
+    if !(0.0..=1.0).contains(&self.inner) {
+      errors.push((inner, OutOfRange(0.0, 1.0)));
+    }
+
+    if !(0.0..=1.0).contains(&self.outer) {
+      errors.push((outer, OutOfRange(0.0, 1.0)));
+    }
+
+    if self.inner >= self.outer {
+      errors.push((inner, NotLessThan(outer.into())));
+    }
+
+    errors
+  }
+}
+
+/// Whether a normalized distance from the center would be emitted as zero.
+fn is_centered(norm: f64) -> bool {
+  norm * MAX_STICK_TILT < 0.5
+}
+
+/// Scales `(x, y)` so that its `norm` becomes `remap(norm)`.
+fn scale(x: f64, y: f64, norm: f64, remap: impl Fn(f64) -> f64) -> (f64, f64) {
+  if is_centered(norm) {
+    return (0.0, 0.0);
+  }
+  let factor = remap(norm) / norm;
+  (x * factor, y * factor)
+}
+
+/// Deadzone compensation for both sticks.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct Deadzones {
+  left: Deadzone,
+  right: Deadzone,
+}
+
+impl Deadzones {
+  pub fn left(&self) -> &Deadzone {
+    &self.left
+  }
+
+  pub fn right(&self) -> &Deadzone {
+    &self.right
+  }
+
+  /// Returns every field whose value does not make sense, together with the reason.
+  pub fn validate(&self) -> Vec<(&'static str, ConfigErrorReason)> {
+    let mut errors = self.left.validate("deadzone.left.inner", "deadzone.left.outer");
+    errors.extend(self.right.validate("deadzone.right.inner", "deadzone.right.outer"));
+    errors
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn assert_close((x, y): (f64, f64), (expected_x, expected_y): (f64, f64)) {
+    assert!((x - expected_x).abs() < 1e-6, "{x} != {expected_x}");
+    assert!((y - expected_y).abs() < 1e-6, "{y} != {expected_y}");
+  }
+
+  #[test]
+  fn test_default_deadzone_is_identity() {
+    for shape in [DeadzoneShape::Radial, DeadzoneShape::Axial, DeadzoneShape::Square] {
+      let deadzone = Deadzone::new(shape, 0.0, 1.0);
+
+      assert_close(deadzone.apply(1000.0, -250.0), (1000.0, -250.0));
+    }
+  }
+
+  #[test]
+  fn test_radial_deadzone_keeps_direction() {
+    let deadzone = Deadzone::new(DeadzoneShape::Radial, 0.2, 0.9);
+
+    let (x, y) = deadzone.apply(1.0, 1.0);
+
+    assert!((x.hypot(y) / MAX_STICK_TILT - 0.2).abs() < 1e-3);
+    assert_close((x, y), (y, x));
+    assert_close(deadzone.apply(MAX_STICK_TILT, 0.0), (0.9 * MAX_STICK_TILT, 0.0));
+  }
+
+  #[test]
+  fn test_axial_deadzone_lifts_every_moving_axis() {
+    let deadzone = Deadzone::new(DeadzoneShape::Axial, 0.25, 1.0);
+
+    let (x, y) = deadzone.apply(-1.0, 0.0);
+
+    assert!(x < -0.25 * MAX_STICK_TILT);
+    assert_eq!(y, 0.0);
+  }
+
+  #[test]
+  fn test_square_deadzone_lifts_the_longer_axis() {
+    let deadzone = Deadzone::new(DeadzoneShape::Square, 0.3, 1.0);
+
+    let (x, y) = deadzone.apply(2.0, 1.0);
+
+    assert!(x >= 0.3 * MAX_STICK_TILT);
+    assert!((x / y - 2.0).abs() < 1e-9);
+  }
+}
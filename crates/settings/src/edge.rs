@@ -0,0 +1,56 @@
+use crate::ConfigErrorReason;
+
+use serde::{Deserialize, Serialize};
+
+use std::time::Duration;
+
+/// Latching of the right stick while the mouse keeps pushing in one direction, so that turning
+/// does not stop when the mouse runs out of room.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct Edge {
+  distance: f64,
+  #[serde(deserialize_with = "crate::from_millis", serialize_with = "crate::to_millis")]
+  window: Duration,
+}
+
+impl Default for Edge {
+  fn default() -> Self {
+    Self {
+      distance: 0.0,
+      window: Duration::from_millis(200),
+    }
+  }
+}
+
+impl Edge {
+  /// How many mouse counts in one direction within `window` latch the stick, `0.0` disables it.
+  pub fn distance(&self) -> f64 {
+    self.distance
+  }
+
+  pub fn window(&self) -> Duration {
+    self.window
+  }
+
+  pub fn is_enabled(&self) -> bool {
+    self.distance > 0.0
+  }
+
+  /// Returns every field whose value does not make sense, together with the reason.
+  pub fn validate(&self) -> Vec<(&'static str, ConfigErrorReason)> {
+    use ConfigErrorReason::*;
+
+    let mut errors = Vec::new();
+
+    if self.distance < 0.0 {
+      errors.push(("edge.distance", AtLeast(0.0)));
+    }
+
+    if self.window.is_zero() {
+      errors.push(("edge.window", Zero));
+    }
+
+    errors
+  }
+}
@@ -0,0 +1,139 @@
+/// A single problem found in a configuration file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigError {
+  origin: String,
+  key: Option<String>,
+  reason: ConfigErrorReason,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConfigErrorReason {
+  Io(String),
+  Syntax(String),
+  InvalidType(String),
+  UnknownKey(String),
+  UnknownButton,
+  Zero,
+  Negative,
+  NotPositive,
+  OutOfRange(f64, f64),
+  GreaterThan(String),
+  NotLessThan(String),
+  UnsupportedVersion(u32),
+  UnknownField,
+  Invalid(String),
+  AtLeast(f64),
+}
+
+impl ConfigError {
+  pub fn new(origin: impl Into<String>, key: Option<&str>, reason: ConfigErrorReason) -> Self {
+    Self {
+      origin: origin.into(),
+      key: key.map(str::to_owned),
+      reason,
+    }
+  }
+
+  pub fn origin(&self) -> &str {
+    &self.origin
+  }
+
+  pub fn key(&self) -> Option<&str> {
+    self.key.as_deref()
+  }
+
+  pub fn reason(&self) -> &ConfigErrorReason {
+    &self.reason
+  }
+}
+
+impl std::fmt::Display for ConfigError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match &self.key {
+      Some(key) => write!(f, "{}: `{}` {}", self.origin, key, self.reason),
+      None => write!(f, "{}: {}", self.origin, self.reason),
+    }
+  }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl std::fmt::Display for ConfigErrorReason {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      ConfigErrorReason::Io(reason) => write!(f, "could not be read: {}", reason),
+      ConfigErrorReason::Syntax(reason) => write!(f, "is not valid TOML: {}", reason),
+      ConfigErrorReason::InvalidType(reason) => write!(f, "has an invalid type: {}", reason),
+      ConfigErrorReason::UnknownKey(key) => write!(f, "contains unknown key name '{}'", key),
+      ConfigErrorReason::UnknownButton => write!(f, "is not a controller button or modifier"),
+      ConfigErrorReason::Zero => write!(f, "must not be zero"),
+      ConfigErrorReason::Negative => write!(f, "must not be negative"),
+      ConfigErrorReason::NotPositive => write!(f, "must be greater than zero"),
+      ConfigErrorReason::OutOfRange(min, max) => {
+        write!(f, "must be between {} and {}", min, max)
+      }
+      ConfigErrorReason::GreaterThan(other) => write!(f, "must not be greater than `{}`", other),
+      ConfigErrorReason::NotLessThan(other) => write!(f, "must be less than `{}`", other),
+      ConfigErrorReason::UnsupportedVersion(version) => write!(
+        f,
+        "is {}, but this version of JoyMouse only supports up to {}",
+        version,
+        crate::CONFIG_VERSION
+      ),
+      ConfigErrorReason::UnknownField => write!(f, "is not a known field and is ignored"),
+      ConfigErrorReason::Invalid(reason) => write!(f, "is invalid: {}", reason),
+      ConfigErrorReason::AtLeast(min) => write!(f, "must be at least {}", min),
+    }
+  }
+}
+
+/// Every problem found while loading the configuration.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ConfigErrors(Vec<ConfigError>);
+
+impl ConfigErrors {
+  pub fn push(&mut self, error: ConfigError) {
+    self.0.push(error);
+  }
+
+  pub fn extend(&mut self, errors: ConfigErrors) {
+    self.0.extend(errors.0);
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.0.is_empty()
+  }
+
+  pub fn errors(&self) -> &[ConfigError] {
+    &self.0
+  }
+
+  /// Returns `Ok(value)` if no problems were collected.
+  pub fn into_result<T>(self, value: T) -> Result<T, Self> {
+    if self.is_empty() {
+      Ok(value)
+    } else {
+      Err(self)
+    }
+  }
+}
+
+impl From<ConfigError> for ConfigErrors {
+  fn from(value: ConfigError) -> Self {
+    Self(vec![value])
+  }
+}
+
+impl std::fmt::Display for ConfigErrors {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    for (i, error) in self.0.iter().enumerate() {
+      if i > 0 {
+        writeln!(f)?;
+      }
+      write!(f, "{}", error)?;
+    }
+    Ok(())
+  }
+}
+
+impl std::error::Error for ConfigErrors {}
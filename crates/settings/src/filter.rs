@@ -0,0 +1,230 @@
+use crate::ConfigErrorReason;
+
+use serde::{Deserialize, Serialize};
+
+use std::sync::LazyLock;
+
+/// The stick filters of the `motion` mapper while `[[filters.stick]]` is not set. The other
+/// mappers do not filter their positions by default.
+pub static MOTION_SMOOTHING: LazyLock<Vec<Filter>> = LazyLock::new(|| {
+  vec![
+    Filter::new(FilterKind::Ema),
+    Filter::new(FilterKind::MagnitudeLock),
+    Filter::new(FilterKind::AngleLock),
+  ]
+});
+
+/// The algorithm of a [`Filter`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FilterKind {
+  /// Exponential moving average, which moves `alpha` of the way towards each new value.
+  #[default]
+  Ema,
+  /// The 1€ filter, which smooths slow movement with `min_cutoff` and follows fast movement
+  /// more closely the higher `beta` is.
+  OneEuro,
+  /// The median of each axis over the last `window` values, which drops single outliers.
+  Median,
+  /// Keeps the previous direction while it changes by less than `limit` degrees.
+  AngleLock,
+  /// Keeps the previous length while it changes by less than `threshold`.
+  MagnitudeLock,
+}
+
+/// A single stage of a filter chain, with the parameters of every kind of filter.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "snake_case")]
+pub struct Filter {
+  kind: FilterKind,
+  alpha: f64,
+  min_cutoff: f64,
+  beta: f64,
+  derivative_cutoff: f64,
+  window: usize,
+  limit: f64,
+  threshold: f64,
+}
+
+impl Default for Filter {
+  fn default() -> Self {
+    Self {
+      kind: FilterKind::Ema,
+      alpha: 0.2,
+      min_cutoff: 1.0,
+      beta: 0.001,
+      derivative_cutoff: 1.0,
+      window: 5,
+      limit: 0.5,
+      threshold: 200.0,
+    }
+  }
+}
+
+impl Filter {
+  pub fn new(kind: FilterKind) -> Self {
+    Self {
+      kind,
+      ..Default::default()
+    }
+  }
+
+  pub fn with_alpha(mut self, alpha: f64) -> Self {
+    self.alpha = alpha;
+    self
+  }
+
+  pub fn with_window(mut self, window: usize) -> Self {
+    self.window = window;
+    self
+  }
+
+  pub fn with_limit(mut self, limit: f64) -> Self {
+    self.limit = limit;
+    self
+  }
+
+  pub fn with_threshold(mut self, threshold: f64) -> Self {
+    self.threshold = threshold;
+    self
+  }
+
+  pub fn kind(&self) -> FilterKind {
+    self.kind
+  }
+
+  /// How far an `ema` moves towards each new value, from `0.0` (never) to `1.0` (at once).
+  pub fn alpha(&self) -> f64 {
+    self.alpha
+  }
+
+  /// The cutoff frequency in Hz of a `one_euro` filter while the input stands still.
+  pub fn min_cutoff(&self) -> f64 {
+    self.min_cutoff
+  }
+
+  /// How much the cutoff frequency of a `one_euro` filter rises with the speed of the input.
+  pub fn beta(&self) -> f64 {
+    self.beta
+  }
+
+  /// The cutoff frequency in Hz with which a `one_euro` filter smooths the speed of the input.
+  pub fn derivative_cutoff(&self) -> f64 {
+    self.derivative_cutoff
+  }
+
+  /// How many values a `median` filter looks at.
+  pub fn window(&self) -> usize {
+    self.window
+  }
+
+  /// The change of the angle in degrees below which an `angle_lock` keeps the previous one.
+  pub fn limit(&self) -> f64 {
+    self.limit
+  }
+
+  /// The change of the length below which a `magnitude_lock` keeps the previous one.
+  pub fn threshold(&self) -> f64 {
+    self.threshold
+  }
+
+  fn validate(&self, chain: &'static str, index: usize) -> Vec<(&'static str, ConfigErrorReason)> {
+    use ConfigErrorReason::*;
+
+    let invalid = |field: &str, reason: ConfigErrorReason| {
+      (chain, Invalid(format!("`{field}` of filter {} {reason}", index + 1)))
+    };
+
+    match self.kind {
+      FilterKind::Ema if !(self.alpha > 0.0 && self.alpha <= 1.0) => {
+        vec![invalid("alpha", OutOfRange(0.0, 1.0))]
+      }
+      FilterKind::OneEuro => {
+        let mut errors = Vec::new();
+        if self.min_cutoff <= 0.0 {
+          errors.push(invalid("min_cutoff", NotPositive));
+        }
+        if self.beta < 0.0 {
+          errors.push(invalid("beta", Negative));
+        }
+        if self.derivative_cutoff <= 0.0 {
+          errors.push(invalid("derivative_cutoff", NotPositive));
+        }
+        errors
+      }
+      FilterKind::Median if self.window == 0 => vec![invalid("window", Zero)],
+      FilterKind::AngleLock if self.limit < 0.0 => vec![invalid("limit", Negative)],
+      FilterKind::MagnitudeLock if self.threshold < 0.0 => vec![invalid("threshold", Negative)],
+      _ => Vec::new(),
+    }
+  }
+}
+
+/// Filter chains for the mouse movement before it reaches the mapper and for the right stick
+/// positions the mapper makes of it, each applied in order.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct Filters {
+  #[serde(default)]
+  mouse: Vec<Filter>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  stick: Option<Vec<Filter>>,
+}
+
+impl Filters {
+  /// A single filter in each chain, so that serializing them names every key of a filter.
+  pub(crate) fn with_every_key() -> Self {
+    Self {
+      mouse: vec![Filter::default()],
+      stick: Some(vec![Filter::default()]),
+    }
+  }
+
+  pub fn mouse(&self) -> &[Filter] {
+    &self.mouse
+  }
+
+  /// The stick filters, or `None` if `[[filters.stick]]` is not set and each mapper uses its own.
+  pub fn stick(&self) -> Option<&[Filter]> {
+    self.stick.as_deref()
+  }
+
+  /// Returns every field whose value does not make sense, together with the reason.
+  pub fn validate(&self) -> Vec<(&'static str, ConfigErrorReason)> {
+    let mouse = self.mouse.iter().enumerate();
+    let stick = self.stick.iter().flatten().enumerate();
+    mouse
+      .flat_map(|(index, filter)| filter.validate("filters.mouse", index))
+      .chain(stick.flat_map(|(index, filter)| filter.validate("filters.stick", index)))
+      .collect()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_missing_parameters_use_defaults() {
+    let filters: Filters =
+      toml::from_str("[[stick]]\nkind = \"median\"\nwindow = 3\n[[stick]]\nkind = \"ema\"")
+        .unwrap();
+
+    assert!(filters.mouse().is_empty());
+    let stick = filters.stick().unwrap();
+    assert_eq!(stick[0], Filter::new(FilterKind::Median).with_window(3));
+    assert_eq!(stick[1].alpha(), Filter::default().alpha());
+  }
+
+  #[test]
+  fn test_validate_names_the_filter() {
+    let filters: Filters =
+      toml::from_str("[[mouse]]\nkind = \"ema\"\n[[mouse]]\nkind = \"ema\"\nalpha = 0.0").unwrap();
+
+    let errors = filters.validate();
+
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].0, "filters.mouse");
+    assert!(errors[0].1.to_string().contains("`alpha` of filter 2"));
+  }
+}
@@ -0,0 +1,13 @@
+use serde::{Deserialize, Serialize};
+
+/// How the `motion` mapper reacts to a flick of the mouse.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FlickMode {
+  /// Commits `max_tilt_range` and lets smoothing and the idle timeout bring the stick back.
+  #[default]
+  Tilt,
+  /// Holds a full tilt for as long as the game needs to turn the camera by the angle the mouse
+  /// moved, as configured in `[rate]`, and recenters right after.
+  Pulse,
+}
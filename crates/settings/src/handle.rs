@@ -0,0 +1,64 @@
+use std::sync::{Arc, RwLock};
+
+/// A shareable value that can be swapped atomically while readers hold on to older snapshots.
+#[derive(Debug)]
+pub struct Handle<T> {
+  inner: Arc<RwLock<Arc<T>>>,
+}
+
+impl<T> Clone for Handle<T> {
+  fn clone(&self) -> Self {
+    Self {
+      inner: Arc::clone(&self.inner),
+    }
+  }
+}
+
+impl<T: Default> Default for Handle<T> {
+  fn default() -> Self {
+    Self::new(T::default())
+  }
+}
+
+impl<T> Handle<T> {
+  pub fn new(value: T) -> Self {
+    Self {
+      inner: Arc::new(RwLock::new(Arc::new(value))),
+    }
+  }
+
+  pub fn load(&self) -> Arc<T> {
+    Arc::clone(&self.inner.read().unwrap())
+  }
+
+  pub fn store(&self, value: T) {
+    *self.inner.write().unwrap() = Arc::new(value);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_store_swaps_what_load_returns() {
+    let handle = Handle::new(1);
+    let clone = handle.clone();
+
+    handle.store(2);
+
+    assert_eq!(*handle.load(), 2);
+    assert_eq!(*clone.load(), 2);
+  }
+
+  #[test]
+  fn test_snapshots_outlive_a_store() {
+    let handle = Handle::new(String::from("old"));
+    let snapshot = handle.load();
+
+    handle.store(String::from("new"));
+
+    assert_eq!(*snapshot, "old");
+    assert_eq!(*handle.load(), "new");
+  }
+}
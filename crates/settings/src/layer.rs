@@ -0,0 +1,39 @@
+/// A set of values applied on top of `joymouse.toml`, e.g. the `[settings]` table of a profile.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Layer {
+  origin: String,
+  table: toml::Table,
+}
+
+impl Layer {
+  pub fn new(origin: impl Into<String>, table: toml::Table) -> Self {
+    Self {
+      origin: origin.into(),
+      table,
+    }
+  }
+
+  pub fn origin(&self) -> &str {
+    &self.origin
+  }
+
+  pub fn table(&self) -> &toml::Table {
+    &self.table
+  }
+
+  /// Whether this layer sets the value at the dotted `key` path.
+  pub fn contains(&self, key: &str) -> bool {
+    let mut table = &self.table;
+    let mut segments = key.split('.').peekable();
+
+    while let Some(segment) = segments.next() {
+      match table.get(segment) {
+        Some(toml::Value::Table(inner)) if segments.peek().is_some() => table = inner,
+        Some(_) => return segments.peek().is_none(),
+        None => return false,
+      }
+    }
+
+    false
+  }
+}
@@ -0,0 +1,318 @@
+use crate::ConfigErrorReason;
+
+use serde::{Deserialize, Serialize};
+
+use std::time::Duration;
+
+/// How far the left stick is pushed towards a diagonal.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Gate {
+  /// Diagonals have the same length as straight directions, like a stick in a round gate.
+  #[default]
+  Circular,
+  /// Diagonals push both axes as far as straight directions would, i.e. into the corners.
+  Square,
+}
+
+/// What the left stick does while `left` and `right` are held at the same time.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HorizontalSocd {
+  /// Neither direction wins and the axis stays centered.
+  #[default]
+  Neutral,
+  /// The key that was pressed last wins.
+  Last,
+  /// The key that was pressed first wins.
+  First,
+  /// `left` always wins.
+  Left,
+  /// `right` always wins.
+  Right,
+}
+
+/// What the left stick does while `up` and `down` are held at the same time.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VerticalSocd {
+  /// Neither direction wins and the axis stays centered.
+  #[default]
+  Neutral,
+  /// The key that was pressed last wins.
+  Last,
+  /// The key that was pressed first wins.
+  First,
+  /// `up` always wins.
+  Up,
+  /// `down` always wins.
+  Down,
+}
+
+/// Resolution of simultaneous opposite cardinal directions, for each axis of the left stick.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct Socd {
+  horizontal: HorizontalSocd,
+  vertical: VerticalSocd,
+}
+
+impl Socd {
+  pub fn new(horizontal: HorizontalSocd, vertical: VerticalSocd) -> Self {
+    Self {
+      horizontal,
+      vertical,
+    }
+  }
+
+  pub fn horizontal(&self) -> HorizontalSocd {
+    self.horizontal
+  }
+
+  pub fn vertical(&self) -> VerticalSocd {
+    self.vertical
+  }
+}
+
+/// How the tilt of the left stick changes over the attack and the release of a [`Ramp`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RampShape {
+  /// No ramp, the stick jumps to the tilt of the held keys.
+  #[default]
+  Off,
+  /// The tilt changes at a constant rate.
+  Linear,
+  /// The tilt changes slowly at first and faster towards the end.
+  EaseIn,
+}
+
+impl RampShape {
+  /// How far a ramp got after `progress` of its time, both from `0.0` to `1.0`.
+  pub fn apply(&self, progress: f64) -> f64 {
+    let progress = progress.clamp(0.0, 1.0);
+    match self {
+      RampShape::Off => 1.0,
+      RampShape::Linear => progress,
+      RampShape::EaseIn => progress * progress,
+    }
+  }
+}
+
+/// Gradual tilting of the left stick while movement keys are held, and back on release.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct Ramp {
+  shape: RampShape,
+  start: f64,
+  #[serde(deserialize_with = "crate::from_millis", serialize_with = "crate::to_millis")]
+  attack: Duration,
+  #[serde(deserialize_with = "crate::from_millis", serialize_with = "crate::to_millis")]
+  release: Duration,
+}
+
+impl Default for Ramp {
+  fn default() -> Self {
+    Self {
+      shape: RampShape::Off,
+      start: 0.3,
+      attack: Duration::from_millis(250),
+      release: Duration::from_millis(100),
+    }
+  }
+}
+
+impl Ramp {
+  pub fn shape(&self) -> RampShape {
+    self.shape
+  }
+
+  /// The share of the full tilt from `0.0` to `1.0` at which a key press starts.
+  pub fn start(&self) -> f64 {
+    self.start
+  }
+
+  /// How long the tilt takes to grow from `start` to full while keys are held.
+  pub fn attack(&self) -> Duration {
+    self.attack
+  }
+
+  /// How long the tilt takes to get back to zero once the keys are released.
+  pub fn release(&self) -> Duration {
+    self.release
+  }
+}
+
+/// How far the left stick is tilted for each of the eight directions, from `0.0` to `1.0`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct Magnitudes {
+  north: f64,
+  north_east: f64,
+  east: f64,
+  south_east: f64,
+  south: f64,
+  south_west: f64,
+  west: f64,
+  north_west: f64,
+}
+
+impl Default for Magnitudes {
+  fn default() -> Self {
+    Self {
+      north: 1.0,
+      north_east: 1.0,
+      east: 1.0,
+      south_east: 1.0,
+      south: 1.0,
+      south_west: 1.0,
+      west: 1.0,
+      north_west: 1.0,
+    }
+  }
+}
+
+impl Magnitudes {
+  pub fn north(&self) -> f64 {
+    self.north
+  }
+
+  pub fn north_east(&self) -> f64 {
+    self.north_east
+  }
+
+  pub fn east(&self) -> f64 {
+    self.east
+  }
+
+  pub fn south_east(&self) -> f64 {
+    self.south_east
+  }
+
+  pub fn south(&self) -> f64 {
+    self.south
+  }
+
+  pub fn south_west(&self) -> f64 {
+    self.south_west
+  }
+
+  pub fn west(&self) -> f64 {
+    self.west
+  }
+
+  pub fn north_west(&self) -> f64 {
+    self.north_west
+  }
+}
+
+/// How the movement keys tilt the left stick.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct LeftStick {
+  gate: Gate,
+  forward_boost: f64,
+  magnitude: Magnitudes,
+  socd: Socd,
+  ramp: Ramp,
+  walk: f64,
+  #[serde(default = "default_levels")]
+  levels: Vec<f64>,
+}
+
+fn default_levels() -> Vec<f64> {
+  vec![0.3, 0.6, 1.0]
+}
+
+impl Default for LeftStick {
+  fn default() -> Self {
+    Self {
+      gate: Gate::Circular,
+      forward_boost: 2.0,
+      magnitude: Magnitudes::default(),
+      socd: Socd::default(),
+      ramp: Ramp::default(),
+      walk: 0.5,
+      levels: default_levels(),
+    }
+  }
+}
+
+impl LeftStick {
+  pub fn gate(&self) -> Gate {
+    self.gate
+  }
+
+  /// Multiplier for the tilt while only moving forward, limited to a full tilt.
+  pub fn forward_boost(&self) -> f64 {
+    self.forward_boost
+  }
+
+  pub fn magnitude(&self) -> &Magnitudes {
+    &self.magnitude
+  }
+
+  pub fn socd(&self) -> &Socd {
+    &self.socd
+  }
+
+  pub fn ramp(&self) -> &Ramp {
+    &self.ramp
+  }
+
+  /// The share of the tilt from `0.0` to `1.0` that is left while the `walk` key is held.
+  pub fn walk(&self) -> f64 {
+    self.walk
+  }
+
+  /// The shares of the tilt that the `magnitude_1`, `magnitude_2`, ... keys select.
+  pub fn levels(&self) -> &[f64] {
+    &self.levels
+  }
+
+  /// Returns every field whose value does not make sense, together with the reason.
+  pub fn validate(&self) -> Vec<(&'static str, ConfigErrorReason)> {
+    use ConfigErrorReason::*;
+
+    let mut errors = Vec::new();
+
+    if self.forward_boost < 1.0 {
+      errors.push(("left_stick.forward_boost", AtLeast(1.0)));
+    }
+
+    let magnitude = &self.magnitude;
+    for (key, value) in [
+      ("left_stick.magnitude.north", magnitude.north),
+      ("left_stick.magnitude.north_east", magnitude.north_east),
+      ("left_stick.magnitude.east", magnitude.east),
+      ("left_stick.magnitude.south_east", magnitude.south_east),
+      ("left_stick.magnitude.south", magnitude.south),
+      ("left_stick.magnitude.south_west", magnitude.south_west),
+      ("left_stick.magnitude.west", magnitude.west),
+      ("left_stick.magnitude.north_west", magnitude.north_west),
+    ] {
+      if !(0.0..=1.0).contains(&value) {
+        errors.push((key, OutOfRange(0.0, 1.0)));
+      }
+    }
+
+    if !(0.0..=1.0).contains(&self.ramp.start) {
+      errors.push(("left_stick.ramp.start", OutOfRange(0.0, 1.0)));
+    }
+
+    if !(0.0..=1.0).contains(&self.walk) {
+      errors.push(("left_stick.walk", OutOfRange(0.0, 1.0)));
+    }
+
+    for (index, level) in self.levels.iter().enumerate() {
+      if !(0.0..=1.0).contains(level) {
+        errors.push((
+          "left_stick.levels",
+          Invalid(format!("level {} {}", index + 1, OutOfRange(0.0, 1.0))),
+        ));
+      }
+    }
+
+    errors
+  }
+}
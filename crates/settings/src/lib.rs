@@ -1,50 +1,303 @@
+mod absolute;
+mod calibration;
+mod curve;
+mod deadzone;
+mod edge;
+mod error;
+mod filter;
+mod flick;
+mod handle;
+mod layer;
+mod left_stick;
+mod mapper;
+mod migration;
+mod rate;
+mod recenter;
+mod transform;
+mod trigger;
+mod watcher;
+
+pub use absolute::*;
+pub use calibration::*;
+pub use curve::*;
+pub use deadzone::*;
+pub use edge::*;
+pub use error::*;
+pub use filter::*;
+pub use flick::*;
+pub use handle::*;
+pub use layer::*;
+pub use left_stick::*;
+pub use mapper::*;
+pub use migration::*;
+pub use rate::*;
+pub use recenter::*;
+pub use transform::*;
+pub use trigger::*;
+pub use watcher::*;
+
 use std::{
-  path::PathBuf,
-  sync::{LazyLock, OnceLock},
+  path::{Path, PathBuf},
+  sync::{LazyLock, RwLock},
   time::Duration,
 };
 
-use config::{Config, File};
+use config::{Config, File, FileFormat};
 use directories::BaseDirs;
 use serde::{Deserialize, Serialize};
 
 pub const MAX_STICK_TILT: f64 = 32767.0;
 pub const MIN_STICK_TILT: f64 = -32768.0;
-pub const LEFT_STICK_SENSITIVITY: f64 = 10000.0;
+pub const MAX_TRIGGER_PULL: f64 = 255.0;
+
+/// Prefix of the environment variables that override fields of `joymouse.toml`.
+pub const ENV_PREFIX: &str = "JOYMOUSE_";
+
+pub type SettingsHandle = Handle<ControllerSettings>;
+
+static CONFIG_PATH: RwLock<Option<PathBuf>> = RwLock::new(None);
+
+static OVERRIDES: RwLock<Vec<Layer>> = RwLock::new(Vec::new());
+
+pub fn config_dir() -> Option<PathBuf> {
+  BaseDirs::new().map(|base_dirs| base_dirs.config_dir().join("joymouse"))
+}
+
+pub fn config_path() -> Option<PathBuf> {
+  CONFIG_PATH.read().unwrap().clone().or_else(|| config_dir().map(|dir| dir.join("joymouse.toml")))
+}
+
+/// Reads the settings from `path` instead of `joymouse.toml` in the configuration directory.
+pub fn set_config_path(path: impl Into<PathBuf>) {
+  *CONFIG_PATH.write().unwrap() = Some(path.into());
+}
+
+/// Sets the layers that are applied on top of every other layer whenever settings are loaded.
+///
+/// These are meant for the environment and the command line, which have to win over both
+/// `joymouse.toml` and the active profile.
+pub fn set_overrides(layers: Vec<Layer>) {
+  *OVERRIDES.write().unwrap() = layers;
+}
+
+/// Collects the `JOYMOUSE_<FIELD>` variables of `vars` that name a field of `joymouse.toml`.
+///
+/// Fields of nested tables are separated by a double underscore, e.g. `JOYMOUSE_CURVE__EXPONENT`.
+/// Values are read as TOML and fall back to a plain string if they are not valid TOML.
+pub fn environment(vars: impl IntoIterator<Item = (String, String)>) -> Layer {
+  let fields = fields();
+  let mut table = toml::Table::new();
+
+  for (name, raw) in vars {
+    let Some(key) = name.strip_prefix(ENV_PREFIX).map(str::to_lowercase) else {
+      continue;
+    };
+
+    let mut path: Vec<&str> = key.split("__").collect();
+    if path.iter().any(|segment| segment.is_empty())
+      || path[0] == "version"
+      || !fields.contains_key(path[0])
+    {
+      continue;
+    }
+
+    let value = toml::from_str::<toml::Table>(&format!("value = {raw}"))
+      .ok()
+      .and_then(|mut table| table.remove("value"))
+      .unwrap_or(toml::Value::String(raw));
+
+    let field = path.pop().unwrap();
+    let mut inner = &mut table;
+    for segment in path {
+      let entry = inner.entry(segment).or_insert_with(|| toml::Value::Table(Default::default()));
+      if !entry.is_table() {
+        *entry = toml::Value::Table(Default::default());
+      }
+      inner = entry.as_table_mut().unwrap();
+    }
+    inner.insert(field.to_owned(), value);
+  }
+
+  Layer::new("environment", table)
+}
+
+fn origin() -> String {
+  config_path().map(|path| path.display().to_string()).unwrap_or_else(|| "joymouse.toml".into())
+}
+
+/// Returns where the value at `key` came from, i.e. the last layer that sets it.
+fn origin_of(key: &str, layers: &[Layer]) -> String {
+  layers
+    .iter()
+    .rev()
+    .find(|layer| layer.contains(key))
+    .map(|layer| layer.origin().to_owned())
+    .unwrap_or_else(origin)
+}
 
-fn config() -> &'static Config {
-  static CONFIG: OnceLock<Config> = OnceLock::new();
-  CONFIG.get_or_init(|| {
-    let mut builder = Config::builder();
+/// The fields of `joymouse.toml`, with their default values where they have one.
+fn fields() -> toml::Table {
+  let settings = ControllerSettings {
+    recenter: Recenter::with_every_key(),
+    filters: Filters::with_every_key(),
+    ..Default::default()
+  };
+  toml::Table::try_from(settings).expect("failed to serialize default config")
+}
 
-    if let Some(base_dirs) = BaseDirs::new() {
-      let mut config_path = PathBuf::from(base_dirs.config_dir());
-      config_path.push("joymouse/joymouse.toml");
+/// Returns the dotted path of every key in `table` that is not a field of `joymouse.toml`.
+pub fn unknown_settings(table: &toml::Table) -> Vec<String> {
+  unknown_keys(table, &fields())
+}
+
+fn defaults() -> Config {
+  Config::try_from(&ControllerSettings::default()).expect("failed to serialize default config")
+}
 
-      if !config_path.exists() {
-        if let Some(parent) = config_path.parent() {
-          std::fs::create_dir_all(parent).unwrap();
-        }
+/// Builds the configuration from the defaults, the `joymouse.toml` at `file` and `layers`.
+///
+/// A missing `file` is created with the defaults if `create` is set, and reported otherwise.
+fn config(layers: &[Layer], file: Option<&Path>, create: bool) -> Result<Config, ConfigError> {
+  let mut builder = Config::builder().add_source(defaults());
 
-        let default_settings = ControllerSettings::default();
+  if let Some(config_path) = file {
+    let origin = config_path.display().to_string();
 
-        let toml_str = {
-          toml::to_string_pretty(&default_settings).expect("failed to serialize default config")
-        };
+    if create && !config_path.exists() {
+      let io =
+        |e: std::io::Error| ConfigError::new(&origin, None, ConfigErrorReason::Io(e.to_string()));
 
-        std::fs::write(&config_path, toml_str).expect("failed to write default config file");
+      if let Some(parent) = config_path.parent() {
+        std::fs::create_dir_all(parent).map_err(io)?;
       }
 
-      builder = builder.add_source(File::with_name(config_path.to_str().unwrap()).required(true));
+      let default_settings = ControllerSettings::default();
+
+      let toml_str =
+        toml::to_string_pretty(&default_settings).expect("failed to serialize default config");
+
+      std::fs::write(config_path, toml_str).map_err(io)?;
+    }
+
+    let mut table = read_table(config_path)?;
+
+    report_upgrade(&origin, upgrade(&origin, &mut table, Schema::Settings)?);
+
+    for key in unknown_settings(&table) {
+      warn(&ConfigError::new(&origin, Some(&key), ConfigErrorReason::UnknownField));
     }
 
-    builder.build().unwrap()
+    let table = toml::to_string(&table).expect("failed to serialize config file");
+    builder = builder.add_source(File::from_str(&table, FileFormat::Toml));
+  }
+
+  for layer in layers {
+    let table = toml::to_string(layer.table()).expect("failed to serialize config layer");
+    builder = builder.add_source(File::from_str(&table, FileFormat::Toml));
+  }
+
+  builder.build().map_err(|e| match e {
+    config::ConfigError::FileParse {
+      uri,
+      cause,
+    } => ConfigError::new(
+      uri.unwrap_or_else(origin),
+      None,
+      ConfigErrorReason::Syntax(cause.to_string()),
+    ),
+    e => ConfigError::new(origin(), None, ConfigErrorReason::Io(e.to_string())),
   })
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+pub fn load() -> Result<ControllerSettings, ConfigErrors> {
+  load_with(&[])
+}
+
+/// Loads `joymouse.toml` with each of `layers` applied on top of it in order, followed by the
+/// layers passed to [`set_overrides`].
+///
+/// Fields that are not set anywhere keep their default value. Every invalid field is reported.
+pub fn load_with(layers: &[Layer]) -> Result<ControllerSettings, ConfigErrors> {
+  // An explicit `--config` that does not exist is a mistake, so it is not created.
+  let explicit = CONFIG_PATH.read().unwrap().is_some();
+  load_from(config_path().as_deref(), !explicit, layers)
+}
+
+/// Loads like [`load_with`], but from the `joymouse.toml` at `file`, which is only created if it
+/// is missing and `create` is set.
+fn load_from(
+  file: Option<&Path>,
+  create: bool,
+  layers: &[Layer],
+) -> Result<ControllerSettings, ConfigErrors> {
+  let layers = [layers, &OVERRIDES.read().unwrap()].concat();
+  settings(config(&layers, file, create)?, &layers)
+}
+
+fn settings(config: Config, layers: &[Layer]) -> Result<ControllerSettings, ConfigErrors> {
+  let settings = match config.clone().try_deserialize::<ControllerSettings>() {
+    Ok(settings) => settings,
+    Err(e) => return Err(type_errors(&config, layers, e)),
+  };
+
+  let mut errors = ConfigErrors::default();
+  for (key, reason) in settings.validate() {
+    errors.push(ConfigError::new(origin_of(key, layers), Some(key), reason));
+  }
+
+  errors.into_result(settings)
+}
+
+/// Finds every field that cannot be deserialized by checking each one on its own.
+fn type_errors(config: &Config, layers: &[Layer], error: config::ConfigError) -> ConfigErrors {
+  let mut errors = ConfigErrors::default();
+  let values: config::Map<String, config::Value> =
+    config.clone().try_deserialize().unwrap_or_default();
+
+  let mut keys: Vec<&String> = values.keys().collect();
+  keys.sort();
+
+  for key in keys {
+    let result = Config::builder()
+      .add_source(defaults())
+      .set_override(key.as_str(), values[key].clone())
+      .and_then(|builder| builder.build())
+      .and_then(|config| config.try_deserialize::<ControllerSettings>());
+
+    if let Err(e) = result {
+      errors.push(type_error(e, key, layers));
+    }
+  }
+
+  if errors.is_empty() {
+    errors.push(type_error(error, "", layers));
+  }
+
+  errors
+}
+
+fn type_error(error: config::ConfigError, key: &str, layers: &[Layer]) -> ConfigError {
+  let (key, reason) = match error {
+    config::ConfigError::Type {
+      unexpected,
+      expected,
+      key: Some(key),
+      ..
+    } => (key, format!("expected {expected}, found {unexpected}")),
+    e => (key.to_owned(), e.to_string()),
+  };
+  let key = Some(key.as_str()).filter(|key| !key.is_empty());
+  ConfigError::new(
+    origin_of(key.unwrap_or_default(), layers),
+    key,
+    ConfigErrorReason::InvalidType(reason),
+  )
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub struct ControllerSettings {
+  version: u32,
   #[serde(deserialize_with = "from_millis", serialize_with = "to_millis")]
   tickrate: Duration,
   #[serde(deserialize_with = "from_millis", serialize_with = "to_millis")]
@@ -52,16 +305,28 @@ pub struct ControllerSettings {
   max_tilt_range: f64,
   min_tilt_range: f64,
   sensitivity: f64,
-  blend: f64,
   diagonal_boost: f64,
-  angle_delta_limit: f64,
-  speed_stabilize_threshold: f64,
   min_speed_clamp: f64,
   max_speed_clamp: f64,
   motion_threshold_micro_macro: f64,
   motion_threshold_macro_flick: f64,
   motion_threshold_macro_micro: f64,
   motion_threshold_micro_macro_recover: f64,
+  mapper: MapperKind,
+  preserve_distance: bool,
+  flick: FlickMode,
+  rate: Rate,
+  absolute: Absolute,
+  edge: Edge,
+  recenter: Recenter,
+  #[serde(default)]
+  filters: Filters,
+  calibration: Calibration,
+  curve: ResponseCurve,
+  transform: Transform,
+  left_stick: LeftStick,
+  triggers: Triggers,
+  deadzone: Deadzones,
 }
 
 impl Default for ControllerSettings {
@@ -73,26 +338,56 @@ impl Default for ControllerSettings {
     let max_tilt_range = MAX_STICK_TILT * maximum_tilt;
     let min_tilt_range = MAX_STICK_TILT * minimum_tilt;
     Self {
+      version: CONFIG_VERSION,
       tickrate,
       mouse_idle_timeout,
       max_tilt_range: max_tilt_range.round(),
       min_tilt_range: min_tilt_range.round(),
       sensitivity: 7.0,
-      blend: 0.2,
       diagonal_boost: 1.41,
-      angle_delta_limit: 0.5,
-      speed_stabilize_threshold: 200.0,
       min_speed_clamp: 1.0,
       max_speed_clamp: 500.0,
       motion_threshold_micro_macro: 0.025,
       motion_threshold_macro_flick: 0.5,
       motion_threshold_macro_micro: 0.03,
       motion_threshold_micro_macro_recover: 0.01,
+      mapper: MapperKind::Motion,
+      preserve_distance: false,
+      flick: FlickMode::Tilt,
+      rate: Rate::default(),
+      absolute: Absolute::default(),
+      edge: Edge::default(),
+      recenter: Recenter::default(),
+      filters: Filters::default(),
+      calibration: Calibration::default(),
+      curve: ResponseCurve::default(),
+      transform: Transform::default(),
+      left_stick: LeftStick::default(),
+      triggers: Triggers::default(),
+      deadzone: Deadzones::default(),
     }
   }
 }
 
 impl ControllerSettings {
+  /// Builds settings from the defaults with each of `layers` applied on top, without touching
+  /// `joymouse.toml`.
+  pub fn from_layers(layers: &[Layer]) -> Result<Self, ConfigErrors> {
+    settings(config(layers, None, false)?, layers)
+  }
+
+  /// Builds settings from the defaults with the TOML document `toml`, named `origin` in errors,
+  /// applied on top.
+  pub fn from_toml(origin: &str, toml: &str) -> Result<Self, ConfigErrors> {
+    let table = toml::from_str(toml)
+      .map_err(|e| ConfigError::new(origin, None, ConfigErrorReason::Syntax(e.to_string())))?;
+    Self::from_layers(&[Layer::new(origin, table)])
+  }
+
+  pub const fn version(&self) -> u32 {
+    self.version
+  }
+
   pub const fn tickrate(&self) -> Duration {
     self.tickrate
   }
@@ -101,10 +396,6 @@ impl ControllerSettings {
     self.sensitivity
   }
 
-  pub const fn blend(&self) -> f64 {
-    self.blend
-  }
-
   pub const fn mouse_idle_timeout(&self) -> Duration {
     self.mouse_idle_timeout
   }
@@ -121,14 +412,6 @@ impl ControllerSettings {
     self.diagonal_boost
   }
 
-  pub fn angle_delta_limit(&self) -> f64 {
-    self.angle_delta_limit
-  }
-
-  pub fn speed_stabilize_threshold(&self) -> f64 {
-    self.speed_stabilize_threshold
-  }
-
   pub fn min_speed_clamp(&self) -> f64 {
     self.min_speed_clamp
   }
@@ -152,6 +435,150 @@ impl ControllerSettings {
   pub fn motion_threshold_micro_macro_recover(&self) -> f64 {
     self.motion_threshold_micro_macro_recover
   }
+
+  pub fn mapper(&self) -> MapperKind {
+    self.mapper
+  }
+
+  /// Whether movement the mapper could not emit yet is carried forward and paid out later, so that
+  /// the stick travels as far as the mouse did.
+  pub fn preserve_distance(&self) -> bool {
+    self.preserve_distance
+  }
+
+  pub fn flick(&self) -> FlickMode {
+    self.flick
+  }
+
+  pub fn rate(&self) -> &Rate {
+    &self.rate
+  }
+
+  pub fn absolute(&self) -> &Absolute {
+    &self.absolute
+  }
+
+  pub fn edge(&self) -> &Edge {
+    &self.edge
+  }
+
+  pub fn recenter(&self) -> &Recenter {
+    &self.recenter
+  }
+
+  pub fn filters(&self) -> &Filters {
+    &self.filters
+  }
+
+  pub fn calibration(&self) -> &Calibration {
+    &self.calibration
+  }
+
+  pub fn curve(&self) -> &ResponseCurve {
+    &self.curve
+  }
+
+  pub fn transform(&self) -> &Transform {
+    &self.transform
+  }
+
+  pub fn left_stick(&self) -> &LeftStick {
+    &self.left_stick
+  }
+
+  pub fn triggers(&self) -> &Triggers {
+    &self.triggers
+  }
+
+  pub fn deadzone(&self) -> &Deadzones {
+    &self.deadzone
+  }
+
+  /// Returns every field whose value does not make sense, together with the reason.
+  pub fn validate(&self) -> Vec<(&'static str, ConfigErrorReason)> {
+    use ConfigErrorReason::*;
+
+    let mut errors = Vec::new();
+
+    if self.tickrate.is_zero() {
+      errors.push(("tickrate", Zero));
+    }
+
+    if self.mouse_idle_timeout.is_zero() {
+      errors.push(("mouse_idle_timeout", Zero));
+    }
+
+    if !(0.0..=MAX_STICK_TILT).contains(&self.max_tilt_range) {
+      errors.push(("max_tilt_range", OutOfRange(0.0, MAX_STICK_TILT)));
+    }
+
+    if !(0.0..=MAX_STICK_TILT).contains(&self.min_tilt_range) {
+      errors.push(("min_tilt_range", OutOfRange(0.0, MAX_STICK_TILT)));
+    }
+
+    if self.min_tilt_range > self.max_tilt_range {
+      errors.push(("min_tilt_range", GreaterThan("max_tilt_range".into())));
+    }
+
+    if self.sensitivity <= 0.0 {
+      errors.push(("sensitivity", NotPositive));
+    }
+
+    for (key, value) in
+      [("diagonal_boost", self.diagonal_boost), ("min_speed_clamp", self.min_speed_clamp)]
+    {
+      if value < 0.0 {
+        errors.push((key, Negative));
+      }
+    }
+
+    if self.min_speed_clamp >= self.max_speed_clamp {
+      errors.push(("min_speed_clamp", NotLessThan("max_speed_clamp".into())));
+    }
+
+    if self.motion_threshold_micro_macro >= self.motion_threshold_macro_flick {
+      errors
+        .push(("motion_threshold_micro_macro", NotLessThan("motion_threshold_macro_flick".into())));
+    }
+
+    if self.motion_threshold_macro_micro >= self.motion_threshold_macro_flick {
+      errors
+        .push(("motion_threshold_macro_micro", NotLessThan("motion_threshold_macro_flick".into())));
+    }
+
+    if self.motion_threshold_micro_macro_recover > self.motion_threshold_micro_macro {
+      errors.push((
+        "motion_threshold_micro_macro_recover",
+        GreaterThan("motion_threshold_micro_macro".into()),
+      ));
+    }
+
+    // The `motion` mapper recenters as soon as the residual is paid out, which leaves no room for
+    // a recenter mode or an idle timeout without emitting more than the mouse moved.
+    if self.mapper == MapperKind::Motion && self.preserve_distance {
+      let conflict = || Invalid("cannot be combined with `preserve_distance`".into());
+      if self.recenter.mode() != RecenterMode::Instant {
+        errors.push(("recenter.mode", conflict()));
+      }
+      if self.recenter.idle_timeout().longest().is_some() {
+        errors.push(("recenter.idle_timeout", conflict()));
+      }
+    }
+
+    errors.extend(self.rate.validate());
+    errors.extend(self.absolute.validate());
+    errors.extend(self.edge.validate());
+    errors.extend(self.recenter.validate());
+    errors.extend(self.filters.validate());
+    errors.extend(self.calibration.validate());
+    errors.extend(self.curve.validate());
+    errors.extend(self.transform.validate());
+    errors.extend(self.left_stick.validate());
+    errors.extend(self.triggers.validate());
+    errors.extend(self.deadzone.validate());
+
+    errors
+  }
 }
 
 fn from_millis<'de, D>(deserializer: D) -> Result<Duration, D::Error>
@@ -169,10 +596,216 @@ where
   serializer.serialize_u64(duration.as_millis() as u64)
 }
 
-#[rustfmt::skip]
-pub static SETTINGS: LazyLock<ControllerSettings> = LazyLock::new(|| {
-    config()
-        .clone()
-        .try_deserialize()
-        .expect("invalid config file")
-});
+/// Reads and writes optional durations as milliseconds, leaving them out when they are unset.
+mod optional_millis {
+  use serde::{Deserialize, Deserializer, Serializer};
+
+  use std::time::Duration;
+
+  pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<Duration>, D::Error>
+  where
+    D: Deserializer<'de>,
+  {
+    Ok(Option::<u64>::deserialize(deserializer)?.map(Duration::from_millis))
+  }
+
+  pub fn serialize<S>(duration: &Option<Duration>, serializer: S) -> Result<S::Ok, S::Error>
+  where
+    S: Serializer,
+  {
+    match duration {
+      Some(duration) => serializer.serialize_u64(duration.as_millis() as u64),
+      None => serializer.serialize_none(),
+    }
+  }
+}
+
+/// The active settings, swapped in by whoever loads the configuration files.
+pub static SETTINGS: LazyLock<SettingsHandle> = LazyLock::new(SettingsHandle::default);
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_default_settings_are_valid() {
+    assert!(ControllerSettings::default().validate().is_empty());
+  }
+
+  #[test]
+  fn test_validate_reports_every_problem() {
+    let settings = ControllerSettings {
+      tickrate: Duration::ZERO,
+      min_tilt_range: 20000.0,
+      max_tilt_range: 10000.0,
+      sensitivity: 0.0,
+      motion_threshold_micro_macro: 0.6,
+      ..Default::default()
+    };
+
+    let keys: Vec<&str> = settings.validate().into_iter().map(|(key, _)| key).collect();
+
+    assert_eq!(keys, ["tickrate", "min_tilt_range", "sensitivity", "motion_threshold_micro_macro"]);
+  }
+
+  #[test]
+  fn test_default_config_file_reads_back() {
+    let toml = toml::to_string_pretty(&ControllerSettings::default()).unwrap();
+    let layer = Layer::new("joymouse.toml", toml::from_str(&toml).unwrap());
+
+    let settings = ControllerSettings::from_layers(&[layer]).unwrap();
+
+    assert_eq!(toml::Table::try_from(settings).unwrap(), toml::from_str(&toml).unwrap());
+  }
+
+  #[test]
+  fn test_missing_explicit_config_is_an_error() {
+    let path = std::env::temp_dir().join("joymouse-missing").join("joymouse.toml");
+
+    let errors = load_from(Some(&path), false, &[]).unwrap_err();
+
+    assert!(matches!(errors.errors()[0].reason(), ConfigErrorReason::Io(_)));
+    assert!(!path.exists());
+  }
+
+  #[test]
+  fn test_from_layers_applies_layers_in_order() {
+    let first: toml::Table = toml::from_str("diagonal_boost = 1.1\nsensitivity = 3.0").unwrap();
+    let second: toml::Table = toml::from_str("diagonal_boost = 1.3").unwrap();
+
+    let settings =
+      ControllerSettings::from_layers(&[Layer::new("first", first), Layer::new("second", second)])
+        .unwrap();
+
+    assert_eq!(settings.diagonal_boost(), 1.3);
+    assert_eq!(settings.sensitivity(), 3.0);
+    assert_eq!(settings.tickrate(), ControllerSettings::default().tickrate());
+  }
+
+  #[test]
+  fn test_filter_chains_are_replaced_by_later_layers() {
+    let first = "[[filters.stick]]\nkind = \"ema\"\n[[filters.stick]]\nkind = \"median\"";
+    let second = "[[filters.stick]]\nkind = \"angle_lock\"\nlimit = 2.0";
+    let first = Layer::new("first", toml::from_str(first).unwrap());
+    let second = Layer::new("second", toml::from_str(second).unwrap());
+
+    let settings = ControllerSettings::from_layers(&[first.clone()]).unwrap();
+
+    assert_eq!(settings.filters().stick().map(<[Filter]>::len), Some(2));
+
+    let settings = ControllerSettings::from_layers(&[first, second]).unwrap();
+
+    assert_eq!(
+      settings.filters().stick(),
+      Some(&[Filter::new(FilterKind::AngleLock).with_limit(2.0)][..])
+    );
+  }
+
+  #[test]
+  fn test_stick_filters_can_be_emptied() {
+    let unset = ControllerSettings::default();
+    let empty = ControllerSettings::from_toml("test", "[filters]\nstick = []").unwrap();
+
+    assert_eq!(unset.filters().stick(), None);
+    assert_eq!(empty.filters().stick(), Some(&[][..]));
+  }
+
+  #[test]
+  fn test_from_layers_reports_origin() {
+    let table: toml::Table = toml::from_str("sensitivity = -2.0").unwrap();
+
+    let errors = ControllerSettings::from_layers(&[Layer::new("test", table)]).unwrap_err();
+
+    assert_eq!(errors.errors().len(), 1);
+    assert_eq!(errors.errors()[0].origin(), "test");
+    assert_eq!(errors.errors()[0].key(), Some("sensitivity"));
+  }
+
+  #[test]
+  fn test_preserve_distance_rejects_a_gradual_recenter() {
+    let toml = "preserve_distance = true\n[recenter]\nmode = \"decay\"\n\
+                [recenter.idle_timeout]\nmicro = 96";
+
+    let errors = ControllerSettings::from_toml("test", toml).unwrap_err();
+    let keys: Vec<_> = errors.errors().iter().map(ConfigError::key).collect();
+
+    assert_eq!(keys, [Some("recenter.mode"), Some("recenter.idle_timeout")]);
+
+    let rate = "preserve_distance = true\nmapper = \"rate\"\n[recenter]\nmode = \"decay\"";
+
+    assert!(ControllerSettings::from_toml("test", rate).is_ok());
+  }
+
+  #[test]
+  fn test_unset_optional_fields_are_known() {
+    let table: toml::Table =
+      toml::from_str("[recenter.idle_timeout]\nmicro = 40\nflick = 20\nmicor = 40").unwrap();
+
+    assert_eq!(unknown_settings(&table), ["recenter.idle_timeout.micor"]);
+  }
+
+  #[test]
+  fn test_unknown_filter_parameters_are_reported() {
+    let table: toml::Table =
+      toml::from_str("[[filters.mouse]]\nkind = \"median\"\nwindow = 3\nbogus = 1").unwrap();
+
+    assert_eq!(unknown_settings(&table), ["filters.mouse[0].bogus"]);
+  }
+
+  #[test]
+  fn test_environment_reads_known_fields() {
+    let vars = [
+      ("JOYMOUSE_SENSITIVITY", "9"),
+      ("JOYMOUSE_TICKRATE", "8"),
+      ("JOYMOUSE_PROFILE", "shooter"),
+      ("JOYMOUSE_DIAGONAL_BOOST__VALUE", "1"),
+      ("HOME", "/root"),
+    ]
+    .map(|(name, value)| (name.to_owned(), value.to_owned()));
+
+    let layer = environment(vars);
+
+    assert_eq!(layer.origin(), "environment");
+    assert_eq!(layer.table().len(), 3);
+    assert_eq!(layer.table()["sensitivity"], toml::Value::Integer(9));
+    assert!(layer.contains("diagonal_boost.value"));
+
+    let settings = ControllerSettings::from_layers(&[layer]);
+    assert!(settings.is_err());
+  }
+
+  #[test]
+  fn test_environment_overrides_are_applied() {
+    let vars = [("JOYMOUSE_SENSITIVITY", "9.5"), ("JOYMOUSE_TICKRATE", "8")]
+      .map(|(name, value)| (name.to_owned(), value.to_owned()));
+
+    let settings = ControllerSettings::from_layers(&[environment(vars)]).unwrap();
+
+    assert_eq!(settings.sensitivity(), 9.5);
+    assert_eq!(settings.tickrate(), Duration::from_millis(8));
+  }
+
+  #[test]
+  fn test_curve_table_is_merged_with_defaults() {
+    let table: toml::Table =
+      toml::from_str("[curve]\nkind = \"points\"\npoints = [[0.0, 0.0], [0.5, 0.2], [1.0, 1.0]]")
+        .unwrap();
+
+    let settings = ControllerSettings::from_layers(&[Layer::new("test", table)]).unwrap();
+
+    assert_eq!(settings.curve().kind(), CurveKind::Points);
+    assert!((settings.curve().apply(0.25) - 0.1).abs() < 1e-9);
+  }
+
+  #[test]
+  fn test_layer_contains_dotted_keys() {
+    let table: toml::Table = toml::from_str("blend = 0.1\n[curve]\nexponent = 2.0").unwrap();
+    let layer = Layer::new("profile", table);
+
+    assert!(layer.contains("blend"));
+    assert!(layer.contains("curve.exponent"));
+    assert!(!layer.contains("curve.kind"));
+    assert!(!layer.contains("blend.value"));
+    assert!(!layer.contains("tickrate"));
+  }
+}
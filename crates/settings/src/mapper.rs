@@ -0,0 +1,16 @@
+use serde::{Deserialize, Serialize};
+
+/// The algorithm that turns mouse movement into right stick positions.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MapperKind {
+  /// Classifies movement as micro, macro or flick and smooths the tilt between ticks.
+  #[default]
+  Motion,
+  /// Tilts the stick proportionally to the mouse velocity, so that the camera turns a fixed
+  /// number of degrees per mouse count, as configured in `[rate]`.
+  Rate,
+  /// Moves the stick like a virtual joystick, whose position follows the mouse within a circle
+  /// and is held until moved back, as configured in `[absolute]`.
+  Absolute,
+}
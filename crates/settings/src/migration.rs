@@ -0,0 +1,278 @@
+use crate::{ConfigError, ConfigErrorReason};
+
+use std::path::{Path, PathBuf};
+
+/// The configuration files a migration can upgrade.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Schema {
+  /// `joymouse.toml` and the `[settings]` table of a profile.
+  Settings,
+  /// `bindings.toml` and the `[bindings]` table of a profile.
+  Bindings,
+}
+
+/// Upgrades the tables of one schema version to the next.
+///
+/// Profiles only contain the fields they override, so migrations must cope with missing fields.
+struct Migration {
+  settings: fn(&mut toml::Table),
+  bindings: fn(&mut toml::Table),
+}
+
+const MIGRATIONS: &[Migration] = &[
+  // 0 → 1: Files without a `version` field, which only lack the field itself.
+  Migration {
+    settings: |_| {},
+    bindings: |_| {},
+  },
+  // 1 → 2: The smoothing of the `motion` mapper moved into `[[filters.stick]]`.
+  Migration {
+    settings: smoothing_to_filters,
+    bindings: |_| {},
+  },
+];
+
+/// Replaces `blend`, `speed_stabilize_threshold` and `angle_delta_limit` with the filters that
+/// smooth the same way, in front of the stick filters the table already had.
+///
+/// Only the fields the table sets become stages, since a profile that overrides one of them would
+/// otherwise replace the whole chain of the base file with defaults. Stages that did nothing are
+/// left out. The fields only affected the `motion` mapper, so they are dropped from tables that
+/// select another one.
+fn smoothing_to_filters(table: &mut toml::Table) {
+  let fields =
+    ["blend", "speed_stabilize_threshold", "angle_delta_limit"].map(|key| table.remove(key));
+  if fields.iter().all(Option::is_none) {
+    return;
+  }
+  if table.get("mapper").and_then(toml::Value::as_str).is_some_and(|mapper| mapper != "motion") {
+    return;
+  }
+
+  let [blend, threshold, limit] = fields.map(|value| {
+    value
+      .and_then(|value| value.as_float().or_else(|| value.as_integer().map(|value| value as f64)))
+  });
+  let stages = [
+    ("ema", "alpha", blend, 1.0),
+    ("magnitude_lock", "threshold", threshold, 0.0),
+    ("angle_lock", "limit", limit, 0.0),
+  ];
+  let mut stick: Vec<toml::Value> = stages
+    .into_iter()
+    .filter_map(|(kind, key, value, noop)| Some((kind, key, value?, noop)))
+    .filter(|(_, _, value, noop)| value != noop)
+    .map(|(kind, key, value, _)| {
+      toml::Table::from_iter([("kind".to_owned(), kind.into()), (key.to_owned(), value.into())])
+        .into()
+    })
+    .collect();
+
+  let filters = table.entry("filters").or_insert_with(|| toml::Table::new().into());
+  if let Some(filters) = filters.as_table_mut() {
+    if let Some(toml::Value::Array(previous)) = filters.remove("stick") {
+      stick.extend(previous);
+    }
+    filters.insert("stick".into(), stick.into());
+  }
+}
+
+/// The schema version written to new configuration files.
+pub const CONFIG_VERSION: u32 = MIGRATIONS.len() as u32;
+
+/// Returns the schema version `table` was written for. Files without a version are version 0.
+pub fn version(origin: &str, table: &toml::Table) -> Result<u32, ConfigError> {
+  let error = |reason| ConfigError::new(origin, Some("version"), reason);
+
+  let version = match table.get("version") {
+    None => return Ok(0),
+    Some(toml::Value::Integer(version)) => u32::try_from(*version)
+      .map_err(|_| error(ConfigErrorReason::OutOfRange(0.0, CONFIG_VERSION as f64)))?,
+    Some(value) => {
+      return Err(error(ConfigErrorReason::InvalidType(format!(
+        "expected an integer, found {value}"
+      ))))
+    }
+  };
+
+  if version > CONFIG_VERSION {
+    return Err(error(ConfigErrorReason::UnsupportedVersion(version)));
+  }
+
+  Ok(version)
+}
+
+/// Applies every migration after `version` to a table of `schema`.
+pub fn migrate(table: &mut toml::Table, schema: Schema, version: u32) {
+  for migration in &MIGRATIONS[version as usize..] {
+    match schema {
+      Schema::Settings => (migration.settings)(table),
+      Schema::Bindings => (migration.bindings)(table),
+    }
+  }
+}
+
+/// Upgrades a whole configuration file to [`CONFIG_VERSION`] and returns the version it had.
+pub fn upgrade(origin: &str, table: &mut toml::Table, schema: Schema) -> Result<u32, ConfigError> {
+  let version = version(origin, table)?;
+  migrate(table, schema, version);
+  table.insert("version".into(), toml::Value::Integer(CONFIG_VERSION.into()));
+  Ok(version)
+}
+
+/// Tells the user that `origin` was upgraded in memory only.
+pub fn report_upgrade(origin: &str, version: u32) {
+  if version < CONFIG_VERSION {
+    eprintln!(
+      "{origin} uses config version {version} and was upgraded to version {CONFIG_VERSION} in \
+       memory. Run `joymouse migrate` to update the file."
+    );
+  }
+}
+
+/// Reads the configuration file at `path` as a TOML table.
+pub fn read_table(path: &Path) -> Result<toml::Table, ConfigError> {
+  let origin = path.display().to_string();
+
+  let content = std::fs::read_to_string(path)
+    .map_err(|e| ConfigError::new(&origin, None, ConfigErrorReason::Io(e.to_string())))?;
+
+  toml::from_str(&content)
+    .map_err(|e| ConfigError::new(&origin, None, ConfigErrorReason::Syntax(e.to_string())))
+}
+
+/// Replaces the file at `path` with `table`, keeping the previous file as `<name>.bak`.
+///
+/// Returns the path of the backup.
+pub fn write_upgraded(path: &Path, table: &toml::Table) -> Result<PathBuf, ConfigError> {
+  let origin = path.display().to_string();
+  let io =
+    |e: std::io::Error| ConfigError::new(&origin, None, ConfigErrorReason::Io(e.to_string()));
+
+  let mut backup = path.as_os_str().to_owned();
+  backup.push(".bak");
+  let backup = PathBuf::from(backup);
+
+  let content = toml::to_string_pretty(table).expect("failed to serialize upgraded config");
+
+  std::fs::copy(path, &backup).map_err(io)?;
+  std::fs::write(path, content).map_err(io)?;
+
+  Ok(backup)
+}
+
+/// Returns the dotted path of every key in `table` that does not exist in `known`. The tables of
+/// an array are each checked against the first table of the known array.
+pub fn unknown_keys(table: &toml::Table, known: &toml::Table) -> Vec<String> {
+  let mut unknown = Vec::new();
+
+  for (key, value) in table {
+    match (value, known.get(key)) {
+      (_, None) => unknown.push(key.to_owned()),
+      (toml::Value::Table(inner), Some(toml::Value::Table(known))) => {
+        unknown.extend(unknown_keys(inner, known).into_iter().map(|inner| format!("{key}.{inner}")))
+      }
+      (toml::Value::Array(entries), Some(toml::Value::Array(known))) => {
+        let Some(toml::Value::Table(known)) = known.first() else {
+          continue;
+        };
+        for (index, entry) in entries.iter().enumerate() {
+          if let toml::Value::Table(inner) = entry {
+            unknown.extend(
+              unknown_keys(inner, known).into_iter().map(|inner| format!("{key}[{index}].{inner}")),
+            );
+          }
+        }
+      }
+      _ => {}
+    }
+  }
+
+  unknown
+}
+
+/// Logs a problem that does not prevent the configuration from being used.
+pub fn warn(warning: &ConfigError) {
+  eprintln!("Warning: {warning}");
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_upgrade_unversioned_table() {
+    let mut table: toml::Table = toml::from_str("sensitivity = 9.0").unwrap();
+
+    assert_eq!(upgrade("joymouse.toml", &mut table, Schema::Settings), Ok(0));
+    assert_eq!(table["version"].as_integer(), Some(CONFIG_VERSION.into()));
+    assert_eq!(table["sensitivity"].as_float(), Some(9.0));
+  }
+
+  #[test]
+  fn test_version_rejects_newer_files() {
+    let table: toml::Table = toml::from_str(&format!("version = {}", CONFIG_VERSION + 1)).unwrap();
+
+    let error = version("joymouse.toml", &table).unwrap_err();
+
+    assert_eq!(error.key(), Some("version"));
+    assert_eq!(error.reason(), &ConfigErrorReason::UnsupportedVersion(CONFIG_VERSION + 1));
+  }
+
+  #[test]
+  fn test_smoothing_moves_in_front_of_the_stick_filters() {
+    let mut table: toml::Table = toml::from_str(
+      "version = 1\nblend = 0.5\nangle_delta_limit = 0.0\n[[filters.stick]]\nkind = \"median\"",
+    )
+    .unwrap();
+
+    upgrade("joymouse.toml", &mut table, Schema::Settings).unwrap();
+
+    let expected: toml::Table = toml::from_str(&format!(
+      "version = {CONFIG_VERSION}\n[[filters.stick]]\nkind = \"ema\"\nalpha = 0.5\n\
+       [[filters.stick]]\nkind = \"median\""
+    ))
+    .unwrap();
+    assert_eq!(table, expected);
+  }
+
+  #[test]
+  fn test_profile_overriding_blend_only_gets_an_ema() {
+    let mut table: toml::Table = toml::from_str("version = 1\nblend = 0.3").unwrap();
+
+    upgrade("shooter.toml", &mut table, Schema::Settings).unwrap();
+
+    let expected: toml::Table = toml::from_str("[[stick]]\nkind = \"ema\"\nalpha = 0.3").unwrap();
+    assert_eq!(table["filters"], toml::Value::Table(expected));
+  }
+
+  #[test]
+  fn test_disabled_smoothing_leaves_no_filters() {
+    let mut table: toml::Table = toml::from_str(
+      "version = 1\nblend = 1.0\nangle_delta_limit = 0\nspeed_stabilize_threshold = 0.0",
+    )
+    .unwrap();
+
+    upgrade("joymouse.toml", &mut table, Schema::Settings).unwrap();
+
+    assert_eq!(table["filters"]["stick"], toml::Value::Array(Vec::new()));
+  }
+
+  #[test]
+  fn test_unknown_keys_are_dotted() {
+    let known: toml::Table = toml::from_str("blend = 0.2\n[curve]\nexponent = 1.0").unwrap();
+    let table: toml::Table =
+      toml::from_str("blend = 0.1\nblnd = 0.1\n[curve]\nexponent = 2.0\nexponnet = 2.0").unwrap();
+
+    assert_eq!(unknown_keys(&table, &known), ["blnd", "curve.exponnet"]);
+  }
+
+  #[test]
+  fn test_unknown_keys_look_into_arrays_of_tables() {
+    let known: toml::Table = toml::from_str("[[stick]]\nkind = \"ema\"\nalpha = 0.2").unwrap();
+    let table: toml::Table =
+      toml::from_str("[[stick]]\nkind = \"ema\"\n[[stick]]\nalpha = 0.5\nbogus = 1").unwrap();
+
+    assert_eq!(unknown_keys(&table, &known), ["stick[1].bogus"]);
+  }
+}
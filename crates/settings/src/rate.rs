@@ -0,0 +1,56 @@
+use crate::ConfigErrorReason;
+
+use serde::{Deserialize, Serialize};
+
+/// Calibration of the `rate` mapper, which turns the camera as fast as the mouse moves.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct Rate {
+  max_turn_rate: f64,
+  degrees_per_count: f64,
+}
+
+impl Default for Rate {
+  fn default() -> Self {
+    Self {
+      max_turn_rate: 360.0,
+      degrees_per_count: 0.05,
+    }
+  }
+}
+
+impl Rate {
+  /// How many degrees per second the game turns the camera at full tilt.
+  pub fn max_turn_rate(&self) -> f64 {
+    self.max_turn_rate
+  }
+
+  /// How many degrees the camera should turn per mouse count.
+  pub fn degrees_per_count(&self) -> f64 {
+    self.degrees_per_count
+  }
+
+  /// The tilt from `0.0` to `1.0` that turns the camera as fast as `counts_per_second` should.
+  ///
+  /// Speeds beyond the maximum turn rate of the game result in more than a full tilt.
+  pub fn tilt(&self, counts_per_second: f64) -> f64 {
+    counts_per_second * self.degrees_per_count / self.max_turn_rate
+  }
+
+  /// Returns every field whose value does not make sense, together with the reason.
+  pub fn validate(&self) -> Vec<(&'static str, ConfigErrorReason)> {
+    use ConfigErrorReason::*;
+
+    let mut errors = Vec::new();
+
+    if self.max_turn_rate <= 0.0 {
+      errors.push(("rate.max_turn_rate", NotPositive));
+    }
+
+    if self.degrees_per_count <= 0.0 {
+      errors.push(("rate.degrees_per_count", NotPositive));
+    }
+
+    errors
+  }
+}
@@ -0,0 +1,151 @@
+use crate::{ConfigErrorReason, MAX_STICK_TILT};
+
+use serde::{Deserialize, Serialize};
+
+use std::time::Duration;
+
+/// How the `motion` mapper brings the right stick back to the center once the mouse is idle.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RecenterMode {
+  /// Jumps to the center right away.
+  #[default]
+  Instant,
+  /// Halves the tilt every `half_life`.
+  Decay,
+  /// Lowers the tilt by `deceleration` per second, like a thumb that lets go of the stick.
+  Inertia,
+}
+
+/// How long the mouse has to be idle before the stick recenters, for each kind of motion.
+///
+/// Unset timeouts follow `mouse_idle_timeout`, twice as long for micro motion while walking.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct IdleTimeouts {
+  #[serde(default, skip_serializing_if = "Option::is_none", with = "crate::optional_millis")]
+  idle: Option<Duration>,
+  #[serde(default, skip_serializing_if = "Option::is_none", with = "crate::optional_millis")]
+  micro: Option<Duration>,
+  #[serde(default, skip_serializing_if = "Option::is_none", with = "crate::optional_millis")]
+  r#macro: Option<Duration>,
+  #[serde(default, skip_serializing_if = "Option::is_none", with = "crate::optional_millis")]
+  flick: Option<Duration>,
+}
+
+impl IdleTimeouts {
+  pub fn idle(&self) -> Option<Duration> {
+    self.idle
+  }
+
+  pub fn micro(&self) -> Option<Duration> {
+    self.micro
+  }
+
+  pub fn r#macro(&self) -> Option<Duration> {
+    self.r#macro
+  }
+
+  pub fn flick(&self) -> Option<Duration> {
+    self.flick
+  }
+
+  /// The longest of the configured timeouts.
+  pub fn longest(&self) -> Option<Duration> {
+    [self.idle, self.micro, self.r#macro, self.flick].into_iter().flatten().max()
+  }
+}
+
+/// Recentering of the right stick after the mouse stopped moving.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct Recenter {
+  mode: RecenterMode,
+  #[serde(deserialize_with = "crate::from_millis", serialize_with = "crate::to_millis")]
+  half_life: Duration,
+  deceleration: f64,
+  #[serde(default)]
+  idle_timeout: IdleTimeouts,
+}
+
+impl Default for Recenter {
+  fn default() -> Self {
+    Self {
+      mode: RecenterMode::Instant,
+      half_life: Duration::from_millis(30),
+      deceleration: 4.0,
+      idle_timeout: IdleTimeouts::default(),
+    }
+  }
+}
+
+impl Recenter {
+  pub fn mode(&self) -> RecenterMode {
+    self.mode
+  }
+
+  /// How long `decay` takes to halve the tilt.
+  pub fn half_life(&self) -> Duration {
+    self.half_life
+  }
+
+  /// How many full tilts per second `inertia` takes off the tilt.
+  pub fn deceleration(&self) -> f64 {
+    self.deceleration
+  }
+
+  pub fn idle_timeout(&self) -> &IdleTimeouts {
+    &self.idle_timeout
+  }
+
+  /// The defaults with every timeout set, so that serializing them names every key.
+  pub(crate) fn with_every_key() -> Self {
+    Self {
+      idle_timeout: IdleTimeouts {
+        idle: Some(Duration::ZERO),
+        micro: Some(Duration::ZERO),
+        r#macro: Some(Duration::ZERO),
+        flick: Some(Duration::ZERO),
+      },
+      ..Default::default()
+    }
+  }
+
+  /// How long the stick takes at most to get from a full tilt back to the center.
+  pub fn duration(&self) -> Duration {
+    match self.mode {
+      RecenterMode::Instant => Duration::ZERO,
+      RecenterMode::Decay => self.half_life.mul_f64(MAX_STICK_TILT.log2().ceil()),
+      RecenterMode::Inertia => Duration::from_secs_f64(self.deceleration.recip()),
+    }
+  }
+
+  /// Returns every field whose value does not make sense, together with the reason.
+  pub fn validate(&self) -> Vec<(&'static str, ConfigErrorReason)> {
+    use ConfigErrorReason::*;
+
+    let mut errors = Vec::new();
+
+    if self.half_life.is_zero() {
+      errors.push(("recenter.half_life", Zero));
+    }
+
+    if self.deceleration <= 0.0 {
+      errors.push(("recenter.deceleration", NotPositive));
+    }
+
+    let timeouts = &self.idle_timeout;
+    for (key, timeout) in [
+      ("recenter.idle_timeout.idle", timeouts.idle),
+      ("recenter.idle_timeout.micro", timeouts.micro),
+      ("recenter.idle_timeout.macro", timeouts.r#macro),
+      ("recenter.idle_timeout.flick", timeouts.flick),
+    ] {
+      if timeout.is_some_and(|timeout| timeout.is_zero()) {
+        errors.push((key, Zero));
+      }
+    }
+
+    errors
+  }
+}
@@ -0,0 +1,132 @@
+use crate::ConfigErrorReason;
+
+use serde::{Deserialize, Serialize};
+
+/// Per-axis adjustments applied to raw mouse movement before it is classified, and to the left
+/// stick before it is emitted.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct Transform {
+  sensitivity_x: f64,
+  sensitivity_y: f64,
+  rotation: f64,
+  invert_right_x: bool,
+  invert_right_y: bool,
+  invert_left_x: bool,
+  invert_left_y: bool,
+}
+
+impl Default for Transform {
+  fn default() -> Self {
+    Self {
+      sensitivity_x: 1.0,
+      sensitivity_y: 1.0,
+      rotation: 0.0,
+      invert_right_x: false,
+      invert_right_y: false,
+      invert_left_x: false,
+      invert_left_y: false,
+    }
+  }
+}
+
+impl Transform {
+  pub fn sensitivity_x(&self) -> f64 {
+    self.sensitivity_x
+  }
+
+  pub fn sensitivity_y(&self) -> f64 {
+    self.sensitivity_y
+  }
+
+  pub fn rotation(&self) -> f64 {
+    self.rotation
+  }
+
+  /// Rotates a mouse movement by `rotation` degrees counterclockwise, then scales and inverts
+  /// each axis.
+  pub fn mouse(&self, dx: f64, dy: f64) -> (f64, f64) {
+    let (sin, cos) = self.rotation.to_radians().sin_cos();
+    let x = dx * cos - dy * sin;
+    let y = dx * sin + dy * cos;
+    (
+      invert(x * self.sensitivity_x, self.invert_right_x),
+      invert(y * self.sensitivity_y, self.invert_right_y),
+    )
+  }
+
+  /// Inverts each axis of the left stick as configured.
+  pub fn left_stick(&self, x: f64, y: f64) -> (f64, f64) {
+    (invert(x, self.invert_left_x), invert(y, self.invert_left_y))
+  }
+
+  /// Returns every field whose value does not make sense, together with the reason.
+  pub fn validate(&self) -> Vec<(&'static str, ConfigErrorReason)> {
+    use ConfigErrorReason::*;
+
+    let mut errors = Vec::new();
+
+    if self.sensitivity_x <= 0.0 {
+      errors.push(("transform.sensitivity_x", NotPositive));
+    }
+
+    if self.sensitivity_y <= 0.0 {
+      errors.push(("transform.sensitivity_y", NotPositive));
+    }
+
+    if !(-360.0..=360.0).contains(&self.rotation) {
+      errors.push(("transform.rotation", OutOfRange(-360.0, 360.0)));
+    }
+
+    errors
+  }
+}
+
+fn invert(value: f64, invert: bool) -> f64 {
+  if invert {
+    -value
+  } else {
+    value
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn assert_close((x, y): (f64, f64), (expected_x, expected_y): (f64, f64)) {
+    assert!((x - expected_x).abs() < 1e-9, "{x} != {expected_x}");
+    assert!((y - expected_y).abs() < 1e-9, "{y} != {expected_y}");
+  }
+
+  #[test]
+  fn test_default_transform_is_identity() {
+    let transform = Transform::default();
+
+    assert_close(transform.mouse(3.0, -4.0), (3.0, -4.0));
+    assert_close(transform.left_stick(1.0, -1.0), (1.0, -1.0));
+  }
+
+  #[test]
+  fn test_mouse_is_rotated_before_scaling() {
+    let transform = Transform {
+      rotation: 90.0,
+      sensitivity_y: 0.5,
+      ..Default::default()
+    };
+
+    assert_close(transform.mouse(2.0, 0.0), (0.0, 1.0));
+  }
+
+  #[test]
+  fn test_axes_are_inverted_independently() {
+    let transform = Transform {
+      invert_right_y: true,
+      invert_left_x: true,
+      ..Default::default()
+    };
+
+    assert_close(transform.mouse(1.0, 2.0), (1.0, -2.0));
+    assert_close(transform.left_stick(1.0, 2.0), (-1.0, 2.0));
+  }
+}
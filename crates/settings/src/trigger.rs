@@ -0,0 +1,94 @@
+use crate::ConfigErrorReason;
+
+use serde::{Deserialize, Serialize};
+
+use std::time::Duration;
+
+/// How far a trigger is pulled while its key is held, and how quickly it gets there.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct Trigger {
+  pull: f64,
+  #[serde(deserialize_with = "crate::from_millis", serialize_with = "crate::to_millis")]
+  attack: Duration,
+  #[serde(deserialize_with = "crate::from_millis", serialize_with = "crate::to_millis")]
+  release: Duration,
+}
+
+impl Default for Trigger {
+  fn default() -> Self {
+    Self {
+      pull: 1.0,
+      attack: Duration::ZERO,
+      release: Duration::ZERO,
+    }
+  }
+}
+
+impl Trigger {
+  /// The share of the full pull from `0.0` to `1.0` while the key is held.
+  pub fn pull(&self) -> f64 {
+    self.pull
+  }
+
+  /// How long the trigger takes to go from released to fully pulled.
+  pub fn attack(&self) -> Duration {
+    self.attack
+  }
+
+  /// How long the trigger takes to go from fully pulled back to released.
+  pub fn release(&self) -> Duration {
+    self.release
+  }
+
+  fn validate(&self, pull: &'static str) -> Vec<(&'static str, ConfigErrorReason)> {
+    let mut errors = Vec::new();
+
+    if !(0.0..=1.0).contains(&self.pull) {
+      errors.push((pull, ConfigErrorReason::OutOfRange(0.0, 1.0)));
+    }
+
+    errors
+  }
+}
+
+/// The analog triggers that `l2` and `r2` pull.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct Triggers {
+  digital: bool,
+  left: Trigger,
+  right: Trigger,
+}
+
+impl Default for Triggers {
+  fn default() -> Self {
+    Self {
+      digital: true,
+      left: Trigger::default(),
+      right: Trigger::default(),
+    }
+  }
+}
+
+impl Triggers {
+  /// Whether `l2` and `r2` are also pressed as buttons, next to pulling the trigger axes.
+  pub fn digital(&self) -> bool {
+    self.digital
+  }
+
+  pub fn left(&self) -> &Trigger {
+    &self.left
+  }
+
+  pub fn right(&self) -> &Trigger {
+    &self.right
+  }
+
+  /// Returns every field whose value does not make sense, together with the reason.
+  pub fn validate(&self) -> Vec<(&'static str, ConfigErrorReason)> {
+    let mut errors = self.left.validate("triggers.left.pull");
+    errors.extend(self.right.validate("triggers.right.pull"));
+    errors
+  }
+}
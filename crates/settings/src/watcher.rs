@@ -0,0 +1,64 @@
+use std::{
+  path::{Path, PathBuf},
+  sync::mpsc::{channel, Receiver},
+  time::Duration,
+};
+
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+
+use crate::{config_dir, config_path};
+
+const DEBOUNCE: Duration = Duration::from_millis(100);
+
+/// Watches the configuration directory and the directory of `joymouse.toml` if it lives elsewhere,
+/// and reports which files changed.
+pub struct ConfigWatcher {
+  _watcher: RecommendedWatcher,
+  events: Receiver<PathBuf>,
+}
+
+impl ConfigWatcher {
+  pub fn new() -> notify::Result<Self> {
+    let dir = config_dir().ok_or_else(|| notify::Error::generic("no config directory"))?;
+    let (tx, events) = channel();
+
+    let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+      if let Ok(event) = event {
+        if matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) {
+          for path in event.paths {
+            let _ = tx.send(path);
+          }
+        }
+      }
+    })?;
+
+    watcher.watch(&dir, RecursiveMode::Recursive)?;
+
+    if let Some(parent) = config_path().as_deref().and_then(Path::parent) {
+      if !parent.starts_with(&dir) {
+        watcher.watch(parent, RecursiveMode::NonRecursive)?;
+      }
+    }
+
+    Ok(Self {
+      _watcher: watcher,
+      events,
+    })
+  }
+
+  /// Blocks until files in the configuration directory changed.
+  ///
+  /// Editors usually touch a file several times per save, so events are collected until the
+  /// directory has been quiet for a short moment and every changed path is reported once.
+  pub fn wait(&self) -> Option<Vec<PathBuf>> {
+    let mut paths = vec![self.events.recv().ok()?];
+
+    while let Ok(path) = self.events.recv_timeout(DEBOUNCE) {
+      paths.push(path);
+    }
+
+    paths.sort();
+    paths.dedup();
+    Some(paths)
+  }
+}